@@ -0,0 +1,41 @@
+// cargo bench --bench draw
+//
+// compares Chip8::draw with the previous per-pixel implementation, which
+// worked out each pixel's coordinates with a modulo and a divide
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use chip8::{Chip8, HEIGHT, WIDTH};
+
+fn draw_per_pixel(chip8: &Chip8, frame: &mut [u8]) {
+    for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
+        let x = i % WIDTH as usize;
+        let y = i / WIDTH as usize;
+
+        let rgba = if chip8.pixel(x, y) != 0 {
+            chip8.palette.foreground
+        } else {
+            chip8.palette.background
+        };
+
+        pixel.copy_from_slice(&rgba);
+    }
+}
+
+fn bench_draw(c: &mut Criterion) {
+    // checkerboard, so both colours are drawn
+    let mut chip8 = Chip8::initialize();
+    for x in 0..WIDTH as usize {
+        for y in 0..HEIGHT as usize {
+            chip8.set_pixel(x, y, ((x + y) % 2) as u8);
+        }
+    }
+    let mut frame = vec![0; (WIDTH * HEIGHT * 4) as usize];
+
+    let mut group = c.benchmark_group("draw");
+    group.bench_function("per_pixel", |b| b.iter(|| draw_per_pixel(black_box(&chip8), &mut frame)));
+    group.bench_function("rows", |b| b.iter(|| black_box(&chip8).draw(&mut frame)));
+    group.finish();
+}
+
+criterion_group!(benches, bench_draw);
+criterion_main!(benches);
@@ -0,0 +1,268 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+#[cfg(feature = "sound")]
+use std::sync::Arc;
+#[cfg(feature = "sound")]
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_audio.rs"]
+mod test_audio;
+
+pub const SAMPLE_RATE: u32 = 44_100;
+pub const BUZZER_HZ: u32 = 440;
+const AMPLITUDE: i16 = i16::MAX / 4;
+
+// XO-CHIP audio patterns are 128 one-bit samples, played at 4000 samples a
+// second at the default pitch of 64 and an octave higher every 48 steps
+pub const PATTERN_BITS: usize = 128;
+pub const DEFAULT_PITCH: u8 = 64;
+
+pub fn pattern_rate(pitch: u8) -> f64 {
+    4000.0 * 2f64.powf((pitch as f64 - 64.0) / 48.0)
+}
+
+// what the sound timer is playing this frame
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Tone {
+    Silent,
+    Buzzer,                                 // the fixed square wave
+    Pattern { bits: [u8; 16], rate: f64 },  // an XO-CHIP pattern, looped at `rate` bits a second
+}
+
+// square wave buzzer or XO-CHIP pattern, gated by the sound timer one 60Hz
+// frame at a time
+
+pub struct Buzzer {
+    sample_rate: u32,
+    phase:       u32, // samples into the current wave period
+    position:    f64, // bits into the current pattern
+}
+
+impl Buzzer {
+
+    pub fn new(sample_rate: u32) -> Self {
+        Self {
+            sample_rate,
+            phase: 0,
+            position: 0.0,
+        }
+    }
+
+    // the next sample of `tone`
+    pub fn sample(&mut self, tone: Tone) -> i16 {
+        let period = self.sample_rate / BUZZER_HZ;
+        self.phase = (self.phase + 1) % period;
+        let high = match tone {
+            Tone::Silent => return 0,
+            Tone::Buzzer => self.phase < period / 2,
+            Tone::Pattern { bits, rate } => {
+                self.position = (self.position + rate / self.sample_rate as f64) % PATTERN_BITS as f64;
+                let bit = self.position as usize;
+                bits[bit / 8] >> (7 - bit % 8) & 1 == 1
+            }
+        };
+        if high { AMPLITUDE } else { -AMPLITUDE }
+    }
+
+    // one frame of samples: the tone while the sound timer is running,
+    // silence otherwise
+    pub fn frame(&mut self, tone: Tone) -> Vec<i16> {
+        (0..self.sample_rate / 60).map(|_| self.sample(tone)).collect()
+    }
+}
+
+// the tone passed from the speaker to the audio thread, in atomics rather
+// than behind a lock so the audio thread never waits on the emulator. a read
+// racing a write can mix two patterns for one sample, which is inaudible
+#[cfg(feature = "sound")]
+#[derive(Default)]
+struct SharedTone {
+    kind: AtomicU8,                         // SILENT, BUZZER or PATTERN
+    bits: [AtomicU64; 2],                   // the pattern, 8 bytes each, big-endian
+    rate: AtomicU64,                        // the pattern rate's f64 bits
+}
+
+#[cfg(feature = "sound")]
+const SILENT: u8 = 0;
+#[cfg(feature = "sound")]
+const BUZZER: u8 = 1;
+#[cfg(feature = "sound")]
+const PATTERN: u8 = 2;
+
+#[cfg(feature = "sound")]
+impl SharedTone {
+
+    fn store(&self, tone: Tone) {
+        let kind = match tone {
+            Tone::Silent => SILENT,
+            Tone::Buzzer => BUZZER,
+            Tone::Pattern { bits, rate } => {
+                for (half, chunk) in self.bits.iter().zip(bits.chunks_exact(8)) {
+                    let mut bytes = [0; 8];
+                    bytes.copy_from_slice(chunk);
+                    half.store(u64::from_be_bytes(bytes), Ordering::Relaxed);
+                }
+                self.rate.store(rate.to_bits(), Ordering::Relaxed);
+                PATTERN
+            }
+        };
+        self.kind.store(kind, Ordering::Release);
+    }
+
+    fn load(&self) -> Tone {
+        match self.kind.load(Ordering::Acquire) {
+            BUZZER => Tone::Buzzer,
+            PATTERN => {
+                let mut bits = [0; 16];
+                for (chunk, half) in bits.chunks_exact_mut(8).zip(self.bits.iter()) {
+                    chunk.copy_from_slice(&half.load(Ordering::Relaxed).to_be_bytes());
+                }
+                Tone::Pattern { bits, rate: f64::from_bits(self.rate.load(Ordering::Relaxed)) }
+            }
+            _ => Tone::Silent,
+        }
+    }
+}
+
+// the buzzer as an endless rodio source, playing whatever tone the speaker
+// last set
+#[cfg(feature = "sound")]
+struct ToneSource {
+    buzzer: Buzzer,
+    tone:   Arc<SharedTone>,
+}
+
+#[cfg(feature = "sound")]
+impl Iterator for ToneSource {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        Some(self.buzzer.sample(self.tone.load()) as f32 / AMPLITUDE as f32)
+    }
+}
+
+#[cfg(feature = "sound")]
+impl rodio::Source for ToneSource {
+    fn current_span_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<std::time::Duration> {
+        None
+    }
+}
+
+// reports only the changes in whether the tone should sound, so the
+// speaker starts and stops on timer transitions instead of every frame
+#[derive(Default)]
+pub struct ToneGate {
+    on: bool,
+}
+
+impl ToneGate {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, on: bool) -> Option<bool> {
+        if on == self.on {
+            return None;
+        }
+        self.on = on;
+        Some(on)
+    }
+}
+
+// plays the buzzer through the default output device. without the sound
+// feature there is no device to open, and the buzzer stays silent
+pub struct Speaker {
+    gate:    ToneGate,
+    #[cfg(feature = "sound")]
+    tone:    Arc<SharedTone>,
+    #[cfg(feature = "sound")]
+    sink:    rodio::Sink,
+    #[cfg(feature = "sound")]
+    _stream: rodio::OutputStream,
+}
+
+impl Speaker {
+
+    #[cfg(feature = "sound")]
+    pub fn open() -> Result<Self, String> {
+        let mut stream = rodio::OutputStreamBuilder::open_default_stream().map_err(|err| err.to_string())?;
+        stream.log_on_drop(false);
+        let sink = rodio::Sink::connect_new(stream.mixer());
+        sink.pause();
+        sink.set_volume(0.25);
+        let tone = Arc::new(SharedTone::default());
+        sink.append(ToneSource { buzzer: Buzzer::new(SAMPLE_RATE), tone: Arc::clone(&tone) });
+        Ok(Self { gate: ToneGate::new(), tone, sink, _stream: stream })
+    }
+
+    #[cfg(not(feature = "sound"))]
+    pub fn open() -> Result<Self, String> {
+        Err("built without the sound feature".into())
+    }
+
+    // call once per 60Hz frame with what the sound timer is playing
+    pub fn set(&mut self, tone: Tone) {
+        let on = tone != Tone::Silent;
+        #[cfg(feature = "sound")]
+        self.tone.store(tone);
+        #[cfg(feature = "sound")]
+        match self.gate.update(on) {
+            Some(true) => self.sink.play(),
+            Some(false) => self.sink.pause(),
+            None => {}
+        }
+        #[cfg(not(feature = "sound"))]
+        self.gate.update(on);
+    }
+}
+
+// writes what the buzzer plays to a mono 16-bit WAV file
+
+pub struct Recorder {
+    buzzer: Buzzer,
+    writer: hound::WavWriter<BufWriter<File>>,
+}
+
+impl Recorder {
+
+    pub fn create<P: AsRef<Path>>(path: P) -> Result<Self, hound::Error> {
+        let spec = hound::WavSpec {
+            channels:        1,
+            sample_rate:     SAMPLE_RATE,
+            bits_per_sample: 16,
+            sample_format:   hound::SampleFormat::Int,
+        };
+        Ok(Self {
+            buzzer: Buzzer::new(SAMPLE_RATE),
+            writer: hound::WavWriter::create(path, spec)?,
+        })
+    }
+
+    // record one 60Hz frame
+    pub fn frame(&mut self, tone: Tone) -> Result<(), hound::Error> {
+        for sample in self.buzzer.frame(tone) {
+            self.writer.write_sample(sample)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(self) -> Result<(), hound::Error> {
+        self.writer.finalize()
+    }
+}
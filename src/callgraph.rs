@@ -0,0 +1,17 @@
+use std::collections::BTreeSet;
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_callgraph.rs"]
+mod test_callgraph;
+
+// CALL edges seen during a run as a Graphviz DOT graph, one node per call
+// site and subroutine address
+pub fn to_dot(edges: &BTreeSet<(u16, u16)>) -> String {
+    let mut dot = String::from("digraph calls {\n");
+    for (site, target) in edges {
+        dot.push_str(&format!("    \"{:#05x}\" -> \"{:#05x}\";\n", site, target));
+    }
+    dot.push_str("}\n");
+    dot
+}
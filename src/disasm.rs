@@ -0,0 +1,84 @@
+use crate::instruction::DecodedInstruction;
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_disasm.rs"]
+mod test_disasm;
+
+// static disassembly: every two bytes decoded as an instruction, the same way
+// emulate_cycle decodes them, whether or not the program ever reaches them
+
+// (address, opcode, text) for each instruction in `data`, loaded at
+// `start_addr`. an odd trailing byte is listed as data, and so is the address
+// word after an XO-CHIP F000, which is shown as that instruction's operand
+pub fn disassemble(data: &[u8], start_addr: u16) -> Vec<(u16, u16, String)> {
+    let mut listing: Vec<(u16, u16, String)> = Vec::new();
+    let mut long_load = false;
+    for (n, chunk) in data.chunks(2).enumerate() {
+        let addr = start_addr.wrapping_add(2 * n as u16);
+        let entry = match *chunk {
+            [high, low] if long_load => {
+                let word = (high as u16) << 8 | low as u16;
+                long_load = false;
+                if let Some((_, _, text)) = listing.last_mut() {
+                    *text = format!("LD I, {:#06x}", word);
+                }
+                (addr, word, format!("DW {:#06x}", word))
+            }
+            [high, low] => {
+                let decoded = DecodedInstruction::new((high as u16) << 8 | low as u16);
+                long_load = decoded.class == "F000";
+                (addr, decoded.opcode, text(&decoded))
+            }
+            _ => (addr, chunk[0] as u16, format!("DB {:#04x}", chunk[0])),
+        };
+        listing.push(entry);
+    }
+    listing
+}
+
+// the instruction with its operands, in Cowgod's syntax
+pub fn text(decoded: &DecodedInstruction) -> String {
+    let DecodedInstruction { x, y, n, kk, nnn, class, mnemonic, .. } = *decoded;
+    let vx = format!("V{:X}", x);
+    let vy = format!("V{:X}", y);
+    let operands = match class {
+        "00E0" | "00EE" | "00FB" | "00FC" | "00FD" | "00FE" | "00FF" => String::new(),
+        "00CN"                   => n.to_string(),
+        "1nnn" | "2nnn"          => format!("{:#05x}", nnn),
+        "3xkk" | "4xkk" | "6xkk" | "7xkk" | "Cxkk" => format!("{}, {:#04x}", vx, kk),
+        "5xy0" | "8xy0" | "8xy1" | "8xy2" | "8xy3" | "8xy4" | "8xy5" | "8xy6" | "8xy7" | "8xyE" | "9xy0"
+                                 => format!("{}, {}", vx, vy),
+        "Annn"                   => format!("I, {:#05x}", nnn),
+        "Bnnn"                   => format!("V0, {:#05x}", nnn),
+        "Dxyn"                   => format!("{}, {}, {}", vx, vy, n),
+        "Ex9E" | "ExA1" | "Fx3A" => vx,
+        "F000"                   => "I, long".to_string(), // the address follows in the next two bytes
+        "FN01"                   => x.to_string(),
+        "Fx07"                   => format!("{}, DT", vx),
+        "Fx0A"                   => format!("{}, K", vx),
+        "Fx15"                   => format!("DT, {}", vx),
+        "Fx18"                   => format!("ST, {}", vx),
+        "Fx1E"                   => format!("I, {}", vx),
+        "Fx29"                   => format!("F, {}", vx),
+        "Fx30"                   => format!("HF, {}", vx),
+        "Fx33"                   => format!("B, {}", vx),
+        "Fx55"                   => format!("[I], {}", vx),
+        "Fx65"                   => format!("{}, [I]", vx),
+        "Fx75"                   => format!("R, {}", vx),
+        "Fx85"                   => format!("{}, R", vx),
+        _                        => String::new(),
+    };
+    if operands.is_empty() {
+        mnemonic.to_string()
+    } else {
+        format!("{} {}", mnemonic, operands)
+    }
+}
+
+// the listing as printed by --disasm, one instruction per line
+pub fn to_text(listing: &[(u16, u16, String)]) -> String {
+    listing.iter()
+        .map(|(addr, opcode, text)| format!("{:#05x}  {:04X}  {}\n", addr, opcode, text))
+        .collect()
+}
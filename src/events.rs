@@ -0,0 +1,11 @@
+// things the machine reports to frontends as it runs; see Chip8::subscribe
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    Draw,                        // the display changed (Dxyn or 00E0)
+    SoundStart,                  // the sound timer started counting down
+    SoundStop,                   // the sound timer ran out or was cleared
+    Collision { x: u8, y: u8 },  // a Dxyn at (x, y) erased at least one pixel
+    UnknownOpcode(u16),
+    Exited,                      // 00FD
+}
@@ -0,0 +1,71 @@
+use std::fmt;
+use crate::processor::{Chip8, TIMER_PERIOD};
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_framelog.rs"]
+mod test_framelog;
+
+// the display hash after every 60Hz frame of a run, for catching transient
+// rendering changes that a check of the final state would miss. saved as
+// text, one hex gfx_hash per line
+
+pub fn parse(text: &str) -> Result<Vec<u64>, String> {
+    text.lines()
+        .enumerate()
+        .map(|(n, line)| u64::from_str_radix(line, 16).map_err(|_| format!("frame {}: invalid hash {}", n, line)))
+        .collect()
+}
+
+pub fn to_text(hashes: &[u64]) -> String {
+    hashes.iter().map(|hash| format!("{:016X}\n", hash)).collect()
+}
+
+// one frame's worth of instructions, returning how many ran
+fn run_frame(my_chip8: &mut Chip8) -> usize {
+    my_chip8.run_for(TIMER_PERIOD)
+}
+
+// hash the display after each frame until at least `cycles` instructions
+// have run, or the ROM stops
+pub fn record(my_chip8: &mut Chip8, cycles: usize) -> Vec<u64> {
+    let mut hashes = Vec::new();
+    let mut ran = 0;
+    while ran < cycles {
+        let count = run_frame(my_chip8);
+        if count == 0 {
+            break;
+        }
+        ran += count;
+        hashes.push(my_chip8.gfx_hash());
+    }
+    hashes
+}
+
+// the first frame whose display differs from the reference
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Mismatch {
+    pub frame:    usize,
+    pub expected: u64,
+    pub actual:   u64,
+}
+
+impl fmt::Display for Mismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "frame {} differs: expected {:016X}, got {:016X}", self.frame, self.expected, self.actual)
+    }
+}
+
+impl std::error::Error for Mismatch {}
+
+// run as many frames as the reference has, comparing the display after each
+pub fn verify(my_chip8: &mut Chip8, reference: &[u64]) -> Result<(), Mismatch> {
+    for (frame, &expected) in reference.iter().enumerate() {
+        run_frame(my_chip8);
+        let actual = my_chip8.gfx_hash();
+        if actual != expected {
+            return Err(Mismatch { frame, expected, actual });
+        }
+    }
+    Ok(())
+}
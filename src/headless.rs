@@ -0,0 +1,245 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fs;
+use chip8::{callgraph, disasm, framelog, profile, quirktest, testgen, trace};
+use chip8::processor::{Chip8, FAST_DRAW_THRESHOLD};
+use chip8::srcmap::SourceMap;
+use crate::options::Options;
+
+// instructions shown leading up to a fault
+const FAULT_CONTEXT: usize = 8;
+
+// run whichever headless mode the options select, or return None to start
+// the windowed frontend
+pub fn run(options: &Options) -> Option<Result<(), Box<dyn Error>>> {
+    if let Some(roms) = &options.diff_trace {
+        return Some(diff_trace(&roms[0], &roms[1], options));
+    }
+    if options.disasm {
+        return Some(disasm(options));
+    }
+    if options.canonical_dump {
+        return Some(canonical_dump(options));
+    }
+    if let Some(out) = &options.svg {
+        return Some(svg(out, options));
+    }
+    if options.text_out {
+        return Some(text_out(options));
+    }
+    if options.callgraph {
+        return Some(callgraph(options));
+    }
+    if let Some(out) = &options.profile_csv {
+        return Some(profile_csv(out, options));
+    }
+    if options.quirks_test {
+        return Some(quirks_test(options));
+    }
+    if let Some(out) = &options.record_frames {
+        return Some(record_frames(out, options));
+    }
+    if let Some(reference) = &options.verify_frames {
+        return Some(verify_frames(reference, options));
+    }
+    if let Some(out) = &options.gen_tests {
+        return Some(gen_tests(out, options));
+    }
+    None
+}
+
+// set up a machine for a reproducible run: fixed seed and timers driven by
+// emulated time, since there is no real time to follow
+fn load(path: &str, options: &Options) -> Result<Chip8, Box<dyn Error>> {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.skip_header = options.skip_header;
+    my_chip8.skip_trailer = options.skip_trailer;
+    if let Some(ipf) = options.ipf {
+        my_chip8.set_cycles_per_frame(ipf);
+    }
+    my_chip8.load_fontset();
+    if options.large_font {
+        my_chip8.load_large_font();
+    }
+    if let Some(vip_rom) = &options.vip_rom {
+        my_chip8.load_vip_rom(&fs::read(vip_rom)?)?;
+    }
+    if let Some(octo_map) = &options.octo_map {
+        my_chip8.source_map = Some(SourceMap::parse(&fs::read_to_string(octo_map)?)?);
+    }
+    if let Some(trainer) = &options.trainer {
+        my_chip8.load_trainer(&fs::read(trainer)?)?;
+    }
+    match &options.memory_image {
+        Some(image) if image == path => {
+            my_chip8.load_memory_image(&fs::read(image)?)?;
+            my_chip8.pc = options.entry;
+        }
+        _ => my_chip8.load_program(path)?,
+    }
+    my_chip8.seed(options.seed);
+    my_chip8.set_audio_enabled(false);
+    my_chip8.quirks = options.quirks.quirks();
+    my_chip8.cycle_timers = true;
+    my_chip8.set_colors(options.fg, options.bg);
+    my_chip8.draw_timeout = options.draw_timeout;
+    my_chip8.warn_sprite_write = options.warn_sprite_write;
+    my_chip8.warn_misalign = options.warn_misalign;
+    my_chip8.strict_align = options.strict_align;
+    my_chip8.trace_every = options.trace_every;
+    for watch in &options.watchpoints {
+        my_chip8.add_watchpoint(*watch);
+    }
+    for &addr in &options.breakpoints {
+        my_chip8.add_breakpoint(addr);
+    }
+    Ok(my_chip8)
+}
+
+fn run_cycles(my_chip8: &mut Chip8, cycles: usize) {
+    my_chip8.run_cycles(cycles);
+    report_stop(my_chip8);
+    if let Some(err) = my_chip8.error {
+        eprintln!("stopped: {}", err);
+        let trace = my_chip8.trace();
+        for entry in &trace[trace.len().saturating_sub(FAULT_CONTEXT)..] {
+            eprintln!("    {}", entry);
+        }
+    }
+}
+
+// run statistics, on stderr to keep them out of the output proper
+fn summary(my_chip8: &Chip8, options: &Options) {
+    eprintln!("max stack depth: {}/{}", my_chip8.max_stack_depth(), my_chip8.stack.len());
+    if options.stats {
+        stats(my_chip8);
+    }
+}
+
+// where a watchpoint or breakpoint paused the machine, with its state; on
+// stderr, like the other reports, to keep headless output clean
+pub fn report_stop(my_chip8: &Chip8) {
+    let location = my_chip8.location(my_chip8.pc);
+    if let Some(watch) = my_chip8.watch_hit {
+        eprintln!("watchpoint {:?} hit at {}\n{}", watch, location, my_chip8.canonical_dump());
+    } else if my_chip8.breakpoint_hit.is_some() && my_chip8.paused {
+        eprintln!("breakpoint hit at {}\n{}", location, my_chip8.canonical_dump());
+    }
+}
+
+// --stats, also printed when the windowed frontend closes
+pub fn stats(my_chip8: &Chip8) {
+    match my_chip8.instructions_per_draw() {
+        Some(average) => {
+            eprintln!("instructions per draw: {:.1}", average);
+            if average > FAST_DRAW_THRESHOLD {
+                eprintln!("warning: the ROM draws rarely for its speed; it may expect a lower clock speed");
+            }
+        }
+        None => eprintln!("instructions per draw: no draws"),
+    }
+}
+
+fn rom_path(options: &Options) -> Result<&str, Box<dyn Error>> {
+    Ok(options.rom.as_deref().or(options.memory_image.as_deref()).ok_or("no ROM given")?)
+}
+
+fn diff_trace(rom_a: &str, rom_b: &str, options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut a = load(rom_a, options)?;
+    let mut b = load(rom_b, options)?;
+
+    match trace::diff_traces(&mut a, &mut b, options.cycles, 8) {
+        Some(divergence) => println!("{}", divergence),
+        None => println!("no divergence in {} cycles", options.cycles),
+    }
+    Ok(())
+}
+
+// list the ROM's instructions without running it
+fn disasm(options: &Options) -> Result<(), Box<dyn Error>> {
+    let path = rom_path(options)?;
+    // a memory image starts at address 0, a ROM at 0x200
+    let start = if options.memory_image.as_deref() == Some(path) { 0 } else { 0x200 };
+    print!("{}", disasm::to_text(&disasm::disassemble(&fs::read(path)?, start)));
+    Ok(())
+}
+
+fn canonical_dump(options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut my_chip8 = load(rom_path(options)?, options)?;
+    run_cycles(&mut my_chip8, options.cycles);
+    print!("{}", my_chip8.canonical_dump());
+    summary(&my_chip8, options);
+    Ok(())
+}
+
+fn svg(out: &str, options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut my_chip8 = load(rom_path(options)?, options)?;
+    run_cycles(&mut my_chip8, options.cycles);
+    fs::write(out, my_chip8.to_svg(options.svg_scale))?;
+    summary(&my_chip8, options);
+    Ok(())
+}
+
+fn text_out(options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut my_chip8 = load(rom_path(options)?, options)?;
+    run_cycles(&mut my_chip8, options.cycles);
+    let (on, off) = options.text_chars;
+    print!("{}", my_chip8.to_text(on, off));
+    summary(&my_chip8, options);
+    Ok(())
+}
+
+fn quirks_test(options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut my_chip8 = load(rom_path(options)?, options)?;
+    my_chip8.memory[quirktest::TEST_ADDR] = quirktest::QUIRKS_TEST;
+    my_chip8.memory[quirktest::PLATFORM_ADDR] = quirktest::platform(options.quirks);
+    run_cycles(&mut my_chip8, options.cycles);
+    print!("{}", quirktest::summary(&quirktest::read(&my_chip8)));
+    summary(&my_chip8, options);
+    Ok(())
+}
+
+fn callgraph(options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut my_chip8 = load(rom_path(options)?, options)?;
+    my_chip8.call_edges = Some(BTreeSet::new());
+    run_cycles(&mut my_chip8, options.cycles);
+    print!("{}", callgraph::to_dot(my_chip8.call_edges.as_ref().unwrap()));
+    summary(&my_chip8, options);
+    Ok(())
+}
+
+fn profile_csv(out: &str, options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut my_chip8 = load(rom_path(options)?, options)?;
+    my_chip8.profile = Some(BTreeMap::new());
+    run_cycles(&mut my_chip8, options.cycles);
+    fs::write(out, profile::to_csv(my_chip8.profile.as_ref().unwrap()))?;
+    summary(&my_chip8, options);
+    Ok(())
+}
+
+fn record_frames(out: &str, options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut my_chip8 = load(rom_path(options)?, options)?;
+    let hashes = framelog::record(&mut my_chip8, options.cycles);
+    fs::write(out, framelog::to_text(&hashes))?;
+    eprintln!("recorded {} frames", hashes.len());
+    summary(&my_chip8, options);
+    Ok(())
+}
+
+fn verify_frames(reference: &str, options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut my_chip8 = load(rom_path(options)?, options)?;
+    let reference = framelog::parse(&fs::read_to_string(reference)?)?;
+    framelog::verify(&mut my_chip8, &reference)?;
+    println!("all {} frames match", reference.len());
+    summary(&my_chip8, options);
+    Ok(())
+}
+
+fn gen_tests(out: &str, options: &Options) -> Result<(), Box<dyn Error>> {
+    let mut my_chip8 = load(rom_path(options)?, options)?;
+    let samples = testgen::capture(&mut my_chip8, options.cycles, options.quirks);
+    fs::write(out, testgen::to_rust(&samples, options.quirks))?;
+    eprintln!("generated {} tests", samples.len());
+    summary(&my_chip8, options);
+    Ok(())
+}
@@ -0,0 +1,130 @@
+use std::collections::VecDeque;
+use winit::keyboard::KeyCode;
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_input.rs"]
+mod test_input;
+
+// holds key events back for a number of 60Hz frames before the CPU sees them,
+// to simulate the input latency of real hardware or a slow display
+
+pub struct InputDelay {
+    frames:  u64,
+    frame:   u64,
+    pending: VecDeque<(u64, usize, bool)>, // (due frame, key, pressed)
+}
+
+impl InputDelay {
+
+    pub fn new(frames: u64) -> Self {
+        Self {
+            frames,
+            frame:   0,
+            pending: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, key: usize, pressed: bool) {
+        self.pending.push_back((self.frame + self.frames, key, pressed));
+    }
+
+    // advance one frame and apply whatever became due
+    pub fn tick(&mut self, keys: &mut [bool; 16]) {
+        self.frame += 1;
+        self.apply(keys);
+    }
+
+    // apply events that are due by the current frame, in the order they arrived
+    pub fn apply(&mut self, keys: &mut [bool; 16]) {
+        while let Some(&(due, key, pressed)) = self.pending.front() {
+            if due > self.frame {
+                break;
+            }
+            keys[key] = pressed;
+            self.pending.pop_front();
+        }
+    }
+}
+
+// which keyboard keys press which CHIP-8 keys. the default puts the hex
+// keypad on the left of a QWERTY keyboard:
+//
+// +-+-+-+-+    +-+-+-+-+
+// |1|2|3|C|    |1|2|3|4|
+// +-+-+-+-+    +-+-+-+-+
+// |4|5|6|D|    |Q|W|E|R|
+// +-+-+-+-+ => +-+-+-+-+
+// |7|8|9|E|    |A|S|D|F|
+// +-+-+-+-+    +-+-+-+-+
+// |A|0|B|F|    |Z|X|C|V|
+// +-+-+-+-+    +-+-+-+-+
+//    old          new
+
+pub struct Keymap {
+    binds: Vec<(KeyCode, u8)>,
+}
+
+// keys a keymap file can name, by their winit KeyCode names
+const NAMED_KEYS: &[KeyCode] = &[
+    KeyCode::Digit0, KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3, KeyCode::Digit4,
+    KeyCode::Digit5, KeyCode::Digit6, KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+    KeyCode::KeyA, KeyCode::KeyB, KeyCode::KeyC, KeyCode::KeyD, KeyCode::KeyE, KeyCode::KeyF,
+    KeyCode::KeyG, KeyCode::KeyH, KeyCode::KeyI, KeyCode::KeyJ, KeyCode::KeyK, KeyCode::KeyL,
+    KeyCode::KeyM, KeyCode::KeyN, KeyCode::KeyO, KeyCode::KeyP, KeyCode::KeyQ, KeyCode::KeyR,
+    KeyCode::KeyS, KeyCode::KeyT, KeyCode::KeyU, KeyCode::KeyV, KeyCode::KeyW, KeyCode::KeyX,
+    KeyCode::KeyY, KeyCode::KeyZ,
+    KeyCode::Numpad0, KeyCode::Numpad1, KeyCode::Numpad2, KeyCode::Numpad3, KeyCode::Numpad4,
+    KeyCode::Numpad5, KeyCode::Numpad6, KeyCode::Numpad7, KeyCode::Numpad8, KeyCode::Numpad9,
+    KeyCode::NumpadDivide, KeyCode::NumpadMultiply, KeyCode::NumpadDecimal, KeyCode::NumpadEnter,
+    KeyCode::Comma, KeyCode::Period, KeyCode::Semicolon, KeyCode::Quote, KeyCode::Slash,
+    KeyCode::BracketLeft, KeyCode::BracketRight, KeyCode::Backslash,
+    KeyCode::Space,
+];
+
+impl Default for Keymap {
+    fn default() -> Self {
+        Self::from_pairs(&[
+            (KeyCode::KeyX,   0x0), (KeyCode::Digit1, 0x1), (KeyCode::Digit2, 0x2), (KeyCode::Digit3, 0x3),
+            (KeyCode::KeyQ,   0x4), (KeyCode::KeyW,   0x5), (KeyCode::KeyE,   0x6), (KeyCode::KeyA,   0x7),
+            (KeyCode::KeyS,   0x8), (KeyCode::KeyD,   0x9), (KeyCode::KeyZ,   0xA), (KeyCode::KeyC,   0xB),
+            (KeyCode::Digit4, 0xC), (KeyCode::KeyR,   0xD), (KeyCode::KeyF,   0xE), (KeyCode::KeyV,   0xF),
+        ])
+    }
+}
+
+impl Keymap {
+
+    // keys above 0xF are ignored
+    pub fn from_pairs(pairs: &[(KeyCode, u8)]) -> Self {
+        Self { binds: pairs.iter().copied().filter(|&(_, key)| key < 16).collect() }
+    }
+
+    // one `KeyName = hex key` per line, e.g. `KeyQ = 7`, with # comments.
+    // key names are winit's KeyCode names
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut binds = Vec::new();
+        for (n, line) in text.lines().enumerate() {
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let bind = line.split_once('=')
+                .and_then(|(name, key)| Some((key_code(name.trim())?, u8::from_str_radix(key.trim(), 16).ok()?)))
+                .filter(|&(_, key)| key < 16);
+            match bind {
+                Some(bind) => binds.push(bind),
+                None => return Err(format!("keymap line {}: expected `KeyName = 0-F`, got `{}`", n + 1, line)),
+            }
+        }
+        Ok(Self { binds })
+    }
+
+    pub fn binds(&self) -> &[(KeyCode, u8)] {
+        &self.binds
+    }
+}
+
+fn key_code(name: &str) -> Option<KeyCode> {
+    NAMED_KEYS.iter().copied().find(|code| format!("{:?}", code) == name)
+}
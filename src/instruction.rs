@@ -0,0 +1,145 @@
+// an opcode split into its operand fields
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DecodedInstruction {
+    pub opcode:   u16,
+    pub nibbles:  (u8, u8, u8, u8),
+    pub x:        usize,
+    pub y:        usize,
+    pub n:        usize,
+    pub kk:       u8,
+    pub nnn:      u16,
+    pub class:    &'static str,
+    pub mnemonic: &'static str,
+}
+
+impl DecodedInstruction {
+
+    pub fn new(opcode: u16) -> Self {
+        let nibbles = (
+            ((opcode & 0xF000) >> 12) as u8,
+            ((opcode & 0x0F00) >> 8) as u8,
+            ((opcode & 0x00F0) >> 4) as u8,
+            (opcode & 0x000F) as u8,
+        );
+
+        Self {
+            opcode,
+            nibbles,
+            x:        nibbles.1 as usize,
+            y:        nibbles.2 as usize,
+            n:        nibbles.3 as usize,
+            kk:       (opcode & 0x00FF) as u8,
+            nnn:      opcode & 0x0FFF,
+            class:    class(nibbles),
+            mnemonic: mnemonic(nibbles),
+        }
+    }
+}
+
+fn mnemonic(nibbles: (u8, u8, u8, u8)) -> &'static str {
+    match nibbles {
+        (0x00, 0x00, 0x0c, _)    => "SCD",
+        (0x00, 0x00, 0x0e, 0x00) => "CLS",
+        (0x00, 0x00, 0x0e, 0x0e) => "RET",
+        (0x00, 0x00, 0x0f, 0x0b) => "SCR",
+        (0x00, 0x00, 0x0f, 0x0c) => "SCL",
+        (0x00, 0x00, 0x0f, 0x0d) => "EXIT",
+        (0x00, 0x00, 0x0f, 0x0e) => "LOW",
+        (0x00, 0x00, 0x0f, 0x0f) => "HIGH",
+        (0x01, _, _, _)          => "JP",
+        (0x02, _, _, _)          => "CALL",
+        (0x03, _, _, _)          => "SE",
+        (0x04, _, _, _)          => "SNE",
+        (0x05, _, _, 0x00)       => "SE",
+        (0x06, _, _, _)          => "LD",
+        (0x07, _, _, _)          => "ADD",
+        (0x08, _, _, 0x00)       => "LD",
+        (0x08, _, _, 0x01)       => "OR",
+        (0x08, _, _, 0x02)       => "AND",
+        (0x08, _, _, 0x03)       => "XOR",
+        (0x08, _, _, 0x04)       => "ADD",
+        (0x08, _, _, 0x05)       => "SUB",
+        (0x08, _, _, 0x06)       => "SHR",
+        (0x08, _, _, 0x07)       => "SUBN",
+        (0x08, _, _, 0x0e)       => "SHL",
+        (0x09, _, _, 0x00)       => "SNE",
+        (0x0a, _, _, _)          => "LD",
+        (0x0b, _, _, _)          => "JP",
+        (0x0c, _, _, _)          => "RND",
+        (0x0d, _, _, _)          => "DRW",
+        (0x0e, _, 0x09, 0x0e)    => "SKP",
+        (0x0e, _, 0x0a, 0x01)    => "SKNP",
+        (0x0f, 0x00, 0x00, 0x00) => "LD",
+        (0x0f, _, 0x00, 0x01)    => "PLANE",
+        (0x0f, 0x00, 0x00, 0x02) => "AUDIO",
+        (0x0f, _, 0x00, 0x07)    => "LD",
+        (0x0f, _, 0x00, 0x0a)    => "LD",
+        (0x0f, _, 0x01, 0x05)    => "LD",
+        (0x0f, _, 0x01, 0x08)    => "LD",
+        (0x0f, _, 0x01, 0x0e)    => "ADD",
+        (0x0f, _, 0x02, 0x09)    => "LD",
+        (0x0f, _, 0x03, 0x00)    => "LD",
+        (0x0f, _, 0x03, 0x03)    => "LD",
+        (0x0f, _, 0x03, 0x0a)    => "PITCH",
+        (0x0f, _, 0x05, 0x05)    => "LD",
+        (0x0f, _, 0x06, 0x05)    => "LD",
+        (0x0f, _, 0x07, 0x05)    => "LD",
+        (0x0f, _, 0x08, 0x05)    => "LD",
+        _                        => "???",
+    }
+}
+
+// opcode pattern, e.g. 8xy4, used to group instructions
+fn class(nibbles: (u8, u8, u8, u8)) -> &'static str {
+    match nibbles {
+        (0x00, 0x00, 0x0c, _)    => "00CN",
+        (0x00, 0x00, 0x0e, 0x00) => "00E0",
+        (0x00, 0x00, 0x0e, 0x0e) => "00EE",
+        (0x00, 0x00, 0x0f, 0x0b) => "00FB",
+        (0x00, 0x00, 0x0f, 0x0c) => "00FC",
+        (0x00, 0x00, 0x0f, 0x0d) => "00FD",
+        (0x00, 0x00, 0x0f, 0x0e) => "00FE",
+        (0x00, 0x00, 0x0f, 0x0f) => "00FF",
+        (0x01, _, _, _)          => "1nnn",
+        (0x02, _, _, _)          => "2nnn",
+        (0x03, _, _, _)          => "3xkk",
+        (0x04, _, _, _)          => "4xkk",
+        (0x05, _, _, 0x00)       => "5xy0",
+        (0x06, _, _, _)          => "6xkk",
+        (0x07, _, _, _)          => "7xkk",
+        (0x08, _, _, 0x00)       => "8xy0",
+        (0x08, _, _, 0x01)       => "8xy1",
+        (0x08, _, _, 0x02)       => "8xy2",
+        (0x08, _, _, 0x03)       => "8xy3",
+        (0x08, _, _, 0x04)       => "8xy4",
+        (0x08, _, _, 0x05)       => "8xy5",
+        (0x08, _, _, 0x06)       => "8xy6",
+        (0x08, _, _, 0x07)       => "8xy7",
+        (0x08, _, _, 0x0e)       => "8xyE",
+        (0x09, _, _, 0x00)       => "9xy0",
+        (0x0a, _, _, _)          => "Annn",
+        (0x0b, _, _, _)          => "Bnnn",
+        (0x0c, _, _, _)          => "Cxkk",
+        (0x0d, _, _, _)          => "Dxyn",
+        (0x0e, _, 0x09, 0x0e)    => "Ex9E",
+        (0x0e, _, 0x0a, 0x01)    => "ExA1",
+        (0x0f, 0x00, 0x00, 0x00) => "F000",
+        (0x0f, _, 0x00, 0x01)    => "FN01",
+        (0x0f, 0x00, 0x00, 0x02) => "F002",
+        (0x0f, _, 0x00, 0x07)    => "Fx07",
+        (0x0f, _, 0x00, 0x0a)    => "Fx0A",
+        (0x0f, _, 0x01, 0x05)    => "Fx15",
+        (0x0f, _, 0x01, 0x08)    => "Fx18",
+        (0x0f, _, 0x01, 0x0e)    => "Fx1E",
+        (0x0f, _, 0x02, 0x09)    => "Fx29",
+        (0x0f, _, 0x03, 0x00)    => "Fx30",
+        (0x0f, _, 0x03, 0x03)    => "Fx33",
+        (0x0f, _, 0x03, 0x0a)    => "Fx3A",
+        (0x0f, _, 0x05, 0x05)    => "Fx55",
+        (0x0f, _, 0x06, 0x05)    => "Fx65",
+        (0x0f, _, 0x07, 0x05)    => "Fx75",
+        (0x0f, _, 0x08, 0x05)    => "Fx85",
+        _                        => "????",
+    }
+}
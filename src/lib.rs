@@ -0,0 +1,41 @@
+// the emulator core, usable without the windowed frontend: headless tests,
+// other renderers, or embedding in another program
+//
+//     use chip8::Chip8;
+//
+//     let mut chip8 = Chip8::initialize();
+//     chip8.load_fontset();
+//     chip8.load_program("/path/to/rom")?;
+//     chip8.emulate_cycle();
+
+pub const WIDTH: u32 = 64;
+pub const HEIGHT: u32 = 32;
+// SUPER-CHIP hi-res display, switched on by 00FF
+pub const HIRES_WIDTH: u32 = 128;
+pub const HIRES_HEIGHT: u32 = 64;
+// default instructions per second: 11 per 60Hz frame
+pub const TICK_SPEED: u64 = 660;
+
+pub mod audio;
+pub mod callgraph;
+pub mod disasm;
+pub mod events;
+pub mod framelog;
+pub mod instruction;
+#[cfg(feature = "metrics")]
+pub mod metrics;
+pub mod processor;
+pub mod profile;
+pub mod quirks;
+pub mod quirktest;
+pub mod replay;
+pub mod rpl;
+pub mod sandbox;
+#[cfg(feature = "serde")]
+pub mod savestate;
+pub mod srcmap;
+pub mod testgen;
+pub mod trace;
+pub mod watch;
+
+pub use processor::Chip8;
@@ -1,33 +1,63 @@
-use pixels::{Error, Pixels, SurfaceTexture};
+use pixels::{Error, PixelsBuilder, SurfaceTexture};
 use std::{time::Duration, thread};
 use winit::dpi::LogicalSize;
 use winit::event::{Event, WindowEvent};
 use winit::event_loop::EventLoop;
 use winit::keyboard::KeyCode;
-use winit::window::WindowBuilder;
+use winit::window::{Fullscreen, WindowBuilder};
 use winit_input_helper::WinitInputHelper;
 use log::error;
 use error_iter::ErrorIter;
-use crate::processor::Chip8;
+use chip8::{audio, disasm, replay, rpl, srcmap, Chip8, HEIGHT, WIDTH};
+use chip8::audio::Tone;
+use chip8::instruction::DecodedInstruction;
+use chip8::processor::Chip8Error;
+#[cfg(feature = "metrics")]
+use chip8::metrics;
+use chip8::quirks::Preset;
+use clap::Parser;
+use crate::options::Options;
+use crate::timing::Clock;
 
-const WIDTH: u32 = 64;
-const HEIGHT: u32 = 32;
-const TICK_SPEED: u64 = 500;
+const MEMORY_SIZE: u32 = 4096;
 
-mod processor;
+mod headless;
+mod input;
+mod options;
+mod overlay;
+mod timing;
+#[cfg(feature = "ui")]
+mod ui;
 
 fn main() -> Result<(), Error> {
 
+    let options = Options::parse();
+
+    // the library reports likely ROM bugs as warnings, shown unless RUST_LOG
+    // says otherwise
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("warn")).init();
+
+    // headless modes
+    if let Some(result) = headless::run(&options) {
+        if let Err(err) = result {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     // set up render system
-    env_logger::init();
     let event_loop = EventLoop::new().unwrap();
     let mut input = WinitInputHelper::new();
+    let (buffer_width, buffer_height) = buffer_size((WIDTH, HEIGHT), options.mem_view);
     let window = {
-        let size = LogicalSize::new(1024 as f64, 512 as f64);
+        let scale = options.scale.unwrap_or(if options.mem_view { 8 } else { 16 });
+        let size = LogicalSize::new((buffer_width * scale) as f64, (buffer_height * scale) as f64);
         WindowBuilder::new()
             .with_title("chip8")
             .with_inner_size(size)
             .with_min_inner_size(size)
+            .with_fullscreen(options.fullscreen.then(|| Fullscreen::Borderless(None)))
             .build(&event_loop)
             .unwrap()
     };
@@ -36,47 +66,294 @@ fn main() -> Result<(), Error> {
     let mut pixels = {
         let window_size = window.inner_size();
         let surface_texture = SurfaceTexture::new(window_size.width, window_size.height, &window);
-        Pixels::new(WIDTH, HEIGHT, surface_texture)?
+        // present at the monitor refresh when vsync is on; wgpu falls back to
+        // whichever present mode the platform supports if it is not available
+        PixelsBuilder::new(buffer_width, buffer_height, surface_texture)
+            .enable_vsync(options.vsync)
+            .build()?
     };
 
     // Initialize the Chip8 system and load the game into memory
     let mut my_chip8 = Chip8::initialize();
     my_chip8.load_fontset();
+    if options.large_font {
+        my_chip8.load_large_font();
+    }
+    my_chip8.cycle_timers = options.cycle_timers;
+    my_chip8.set_colors(options.fg, options.bg);
+    my_chip8.draw_timeout = options.draw_timeout;
+    my_chip8.warn_sprite_write = options.warn_sprite_write;
+    my_chip8.warn_misalign = options.warn_misalign;
+    my_chip8.quirks = options.quirks.quirks();
+    my_chip8.strict_align = options.strict_align;
+    my_chip8.trace_every = options.trace_every;
+    my_chip8.skip_header = options.skip_header;
+    my_chip8.skip_trailer = options.skip_trailer;
+    if let Some(ipf) = options.ipf {
+        my_chip8.set_cycles_per_frame(ipf);
+    }
+
+    if let Some(vip_rom) = &options.vip_rom {
+        let loaded = std::fs::read(vip_rom)
+            .map_err(Into::into)
+            .and_then(|data| my_chip8.load_vip_rom(&data));
+        if let Err(err) = loaded {
+            eprintln!("could not load VIP image {}: {}", vip_rom, err);
+            std::process::exit(1);
+        }
+    }
+
+    if let Some(octo_map) = &options.octo_map {
+        let parsed = std::fs::read_to_string(octo_map)
+            .map_err(|err| err.to_string())
+            .and_then(|text| srcmap::SourceMap::parse(&text));
+        match parsed {
+            Ok(map) => my_chip8.source_map = Some(map),
+            Err(err) => {
+                eprintln!("could not load source map {}: {}", octo_map, err);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    let keymap = match &options.keymap {
+        Some(path) => {
+            let parsed = std::fs::read_to_string(path)
+                .map_err(|err| err.to_string())
+                .and_then(|text| input::Keymap::parse(&text));
+            match parsed {
+                Ok(keymap) => keymap,
+                Err(err) => {
+                    eprintln!("could not load keymap {}: {}", path, err);
+                    std::process::exit(1);
+                }
+            }
+        }
+        None => input::Keymap::default(),
+    };
+
+    if let Some(trainer) = &options.trainer {
+        let loaded = std::fs::read(trainer)
+            .map_err(Into::into)
+            .and_then(|data| my_chip8.load_trainer(&data));
+        if let Err(err) = loaded {
+            eprintln!("could not load trainer {}: {}", trainer, err);
+            std::process::exit(1);
+        }
+    }
+
+    let path = match &options.memory_image {
+        Some(image) => {
+            let loaded = std::fs::read(image)
+                .map_err(Into::into)
+                .and_then(|data| my_chip8.load_memory_image(&data));
+            if let Err(err) = loaded {
+                eprintln!("could not load memory image {}: {}", image, err);
+                std::process::exit(1);
+            }
+            my_chip8.pc = options.entry;
+            image.as_str()
+        }
+        None => {
+            let path = options.rom.as_deref().expect("checked before opening the window");
+            if let Err(err) = my_chip8.load_program(path) {
+                eprintln!("could not load {}: {}", path, err);
+                std::process::exit(1);
+            }
+            path
+        }
+    };
+    if options.verify && !my_chip8.verify_fontset() {
+        eprintln!("fontset corrupted after loading {}", path);
+        std::process::exit(1);
+    }
+
+    // F5 starts over from here, with any VIP image or memory image still in
+    // the interpreter region
+    let entry = my_chip8.pc;
+    let mut interpreter = [0; 0x200];
+    interpreter.copy_from_slice(&my_chip8.memory[..0x200]);
 
-    let path = std::env::args().nth(1).expect("No path entered");
-    let _ = my_chip8.load_program(&path);
+    // restore the ROM's saved RPL flags (high scores)
+    let rpl_dir = rpl::data_dir();
+    my_chip8.rpl = rpl::load(&rpl_dir, my_chip8.rom_hash);
 
-    let mut last_frame = std::time::Instant::now();
-    let last_timer = std::time::Instant::now();
+    // a replay plays the whole session once up front to place its keyframes,
+    // then starts again from the first frame
+    let mut replay = options.replay.as_deref().map(|path| {
+        let recording = match replay::Recording::load(std::path::Path::new(path)) {
+            Ok(recording) => recording,
+            Err(err) => {
+                eprintln!("could not load session {}: {}", path, err);
+                std::process::exit(1);
+            }
+        };
+        let timeline = replay::Timeline::build(&mut my_chip8, recording);
+        let frame = timeline.seek(&mut my_chip8, 0);
+        (timeline, frame)
+    });
+
+    // recorded sessions need a known seed to replay
+    let mut session = options.record_input.as_ref().map(|_| {
+        my_chip8.seed(options.seed);
+        replay::Recording::new(options.seed)
+    });
+
+    // watchpoints start from the loaded state
+    for watch in &options.watchpoints {
+        my_chip8.add_watchpoint(*watch);
+    }
+    for &addr in &options.breakpoints {
+        my_chip8.add_breakpoint(addr);
+    }
+
+    #[cfg(feature = "ui")]
+    let mut framework = if options.ui {
+        Some(ui::Framework::new(&event_loop, &window, &pixels))
+    } else {
+        None
+    };
+    #[cfg(not(feature = "ui"))]
+    if options.ui {
+        eprintln!("--ui requires building with the ui feature");
+        std::process::exit(2);
+    }
+
+    #[cfg(feature = "metrics")]
+    let mut metrics = options.metrics_port.map(|port| {
+        let metrics = std::sync::Arc::new(metrics::Metrics::default());
+        if let Err(err) = metrics::serve(port, metrics.clone()) {
+            eprintln!("could not serve metrics on port {}: {}", port, err);
+            std::process::exit(1);
+        }
+        (metrics, my_chip8.subscribe(), metrics::FpsCounter::new(std::time::Instant::now()))
+    });
+    #[cfg(not(feature = "metrics"))]
+    if options.metrics_port.is_some() {
+        eprintln!("--metrics-port requires building with the metrics feature");
+        std::process::exit(2);
+    }
+
+    let clock = timing::SystemClock;
+    let mut timers = timing::TimerTicker::new(&clock);
 
-    let mut frames_since_tick = 0;
+    let mut eco_render = clock.now();
+
+    let min_frame = Duration::from_millis(options.min_frame_ms);
+    let mut last_present = clock.now();
+
+    let mut recorder = match options.record_audio.as_deref().map(audio::Recorder::create).transpose() {
+        Ok(recorder) => recorder,
+        Err(err) => {
+            eprintln!("could not record audio: {}", err);
+            std::process::exit(1);
+        }
+    };
+
+    my_chip8.set_audio_enabled(!options.mute);
+    let mut speaker = if options.mute {
+        None
+    } else {
+        match audio::Speaker::open() {
+            Ok(speaker) => Some(speaker),
+            Err(err) => {
+                eprintln!("no sound: {}", err);
+                None
+            }
+        }
+    };
+
+    let mut overlay = overlay::Overlay::new();
+    let mut buffer_resolution = (WIDTH, HEIGHT);
+    let mut preset = options.quirks;
+    let mut input_delay = input::InputDelay::new(options.input_delay);
+    let mut was_paused = my_chip8.paused;
 
     // emulation loop
     let res = event_loop.run(|event, elwt| {
 
-        // emulate one cycle
-        my_chip8.emulate_cycle();
-
-        // lazy timing implementation
-        if last_frame.elapsed() < Duration::from_secs(1 / TICK_SPEED) {
-            thread::sleep(Duration::from_secs(1 / TICK_SPEED) - last_frame.elapsed());
+        #[cfg(feature = "metrics")]
+        if let Some((metrics, events, _)) = &metrics {
+            metrics.instructions.store(my_chip8.instructions, std::sync::atomic::Ordering::Relaxed);
+            for event in events.try_iter() {
+                metrics.event(event);
+            }
         }
-        println!("DT: {:?}", last_frame.elapsed()); 
-        last_frame = std::time::Instant::now();
-        
-        if frames_since_tick >= TICK_SPEED / 60 {
-            // update timers
-            if my_chip8.delay_timer > 0 {
-                    my_chip8.delay_timer = my_chip8.delay_timer - 1;
+
+        if let Some((timeline, frame)) = &mut replay {
+            // play the session back one recorded frame per 60Hz tick
+            if let Event::AboutToWait = event {
+                if timers.due(&clock) {
+                    if *frame < timeline.frame_count() {
+                        replay::run_frame(&mut my_chip8, timeline.recording.frames[*frame]);
+                        *frame += 1;
+                    }
+                    if my_chip8.draw_flag || overlay.needs_redraw() {
+                        window.request_redraw();
+                    }
+                }
+                elwt.set_control_flow(timing::tick_control_flow(timers.deadline()));
             }
-        
-            if my_chip8.sound_timer > 0 {
-                    println!("BEEP");
-                    my_chip8.sound_timer = my_chip8.sound_timer - 1;
+        } else if options.eco {
+            // run a tick's worth of instructions in one batch, then sleep until
+            // the next tick or until input arrives
+            if let Event::AboutToWait = event {
+                let now = clock.now();
+                if timers.due(&clock) {
+                    if let Some(session) = &mut session {
+                        session.push(&my_chip8.key);
+                    }
+                    if !(options.halt_idle && my_chip8.is_idle()) {
+                        my_chip8.run_cycles(my_chip8.cycles_per_frame());
+                    }
+                    tick_timers(&mut my_chip8, &mut recorder, &mut speaker);
+                    input_delay.tick(&mut my_chip8.key);
+
+                    if (my_chip8.draw_flag || overlay.needs_redraw()) && now.duration_since(eco_render) >= timing::ECO_FRAME_PERIOD {
+                        eco_render = now;
+                        window.request_redraw();
+                    }
+                }
+                elwt.set_control_flow(timing::tick_control_flow(timers.deadline()));
             }
-            frames_since_tick = 0;
         } else {
-            frames_since_tick += 1;
+            // run a frame's worth of instructions on each 60Hz tick, however
+            // many window events arrive in between, unless the ROM is
+            // waiting on input
+            if timers.due(&clock) {
+                if let Some(session) = &mut session {
+                    session.push(&my_chip8.key);
+                }
+                if !(options.halt_idle && my_chip8.is_idle()) {
+                    my_chip8.run_cycles(my_chip8.cycles_per_frame());
+                }
+                tick_timers(&mut my_chip8, &mut recorder, &mut speaker);
+                input_delay.tick(&mut my_chip8.key);
+            }
+
+            // wait for the next tick unless input arrives first
+            if let Event::AboutToWait = event {
+                elwt.set_control_flow(timing::tick_control_flow(timers.deadline()));
+            }
+        }
+
+        // say what stopped the ROM when it pauses by itself
+        if my_chip8.paused && !was_paused {
+            headless::report_stop(&my_chip8);
+        }
+        was_paused = my_chip8.paused;
+
+        // the ROM faulted
+        if let Some(err) = my_chip8.error {
+            eprintln!("stopped: {}", err);
+            elwt.exit();
+            return;
+        }
+
+        // the ROM asked to stop
+        if my_chip8.exited {
+            elwt.exit();
+            return;
         }
 
         // if the draw flag is set, draw the current frame
@@ -85,14 +362,68 @@ fn main() -> Result<(), Error> {
             ..
         } = event
         {
-            if my_chip8.draw_flag {
-                my_chip8.draw(pixels.frame_mut());
+            // memory changes without the ROM drawing, so the memory view is
+            // refreshed on every redraw, as is the ui panel
+            if my_chip8.draw_flag || options.mem_view || options.ui || overlay.needs_redraw() {
+                // 00FE/00FF switch resolution; the window keeps its size and
+                // the buffer is scaled to fit
+                let (width, height) = my_chip8.resolution();
+                if (width, height) != buffer_resolution {
+                    let (buffer_width, buffer_height) = buffer_size((width, height), options.mem_view);
+                    if let Err(err) = pixels.resize_buffer(buffer_width, buffer_height) {
+                        log_error("pixels.resize_buffer", err);
+                        elwt.exit();
+                        return;
+                    }
+                    buffer_resolution = (width, height);
+                }
+                let (display, memory) = pixels.frame_mut().split_at_mut((width * height * 4) as usize);
+                my_chip8.draw(display);
+                overlay.draw(display, width as usize);
+                if options.mem_view {
+                    my_chip8.draw_memory(memory);
+                }
                 my_chip8.draw_flag = false;
-                if let Err(err) = pixels.render() {
+
+                #[cfg(feature = "ui")]
+                let render_result = match framework.as_mut() {
+                    Some(framework) => {
+                        framework.prepare(&window, &mut my_chip8);
+                        pixels.render_with(|encoder, render_target, context| {
+                            context.scaling_renderer.render(encoder, render_target);
+                            framework.render(encoder, render_target, context);
+                            Ok(())
+                        })
+                    }
+                    None => pixels.render(),
+                };
+                #[cfg(not(feature = "ui"))]
+                let render_result = pixels.render();
+
+                if let Err(err) = render_result {
                     log_error("pixels.render", err);
                     elwt.exit();
                     return;
-        }}}
+                }
+
+                #[cfg(feature = "metrics")]
+                if let Some((metrics, _, fps)) = &mut metrics {
+                    metrics.frames.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+                    if let Some(rate) = fps.frame(clock.now()) {
+                        metrics.set_fps(rate);
+                    }
+                }
+
+                // hold fast ROMs back to at most one frame per --min-frame-ms
+                thread::sleep(timing::frame_throttle(last_present, clock.now(), min_frame));
+                last_present = clock.now();
+        }}
+
+        // let the ui panel see window events first
+        #[cfg(feature = "ui")]
+        if let (Some(framework), Event::WindowEvent { event, .. }) = (framework.as_mut(), &event) {
+            framework.handle_event(&window, event);
+        }
 
         // handle input events
         if input.update(&event) {
@@ -103,39 +434,89 @@ fn main() -> Result<(), Error> {
             }
 
             // Keybinds
-            //
-            // +-+-+-+-+    +-+-+-+-+  For example, key at index array[c] is 4.
-            // |1|2|3|C|    |1|2|3|4|                            array[d] is r.
-            // +-+-+-+-+    +-+-+-+-+                                      
-            // |4|5|6|D|    |Q|W|E|R|                                . . .
-            // +-+-+-+-+ => +-+-+-+-+
-            // |7|8|9|E|    |A|S|D|F|
-            // +-+-+-+-+    +-+-+-+-+
-            // |A|0|B|F|    |Z|X|C|V|
-            // +-+-+-+-+    +-+-+-+-+
-            //    old          new
-            //
-            // Resulting Array
-            // x, 1, 2, 3,
-            // q, w, e, a,
-            // s, d, z, c, 
-            // 4, r, f, v
-
-            let keybinds = [
-                KeyCode::KeyX,   KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3,
-                KeyCode::KeyQ,   KeyCode::KeyW,   KeyCode::KeyE,   KeyCode::KeyA,
-                KeyCode::KeyS,   KeyCode::KeyD,   KeyCode::KeyZ,   KeyCode::KeyC,
-                KeyCode::Digit4, KeyCode::KeyR,   KeyCode::KeyF,   KeyCode::KeyV
-            ];
-
-            for i in 0..keybinds.len() {
-                if input.key_pressed(keybinds[i]) {my_chip8.key[i] = 1;}
-                else if input.key_released(keybinds[i]) {my_chip8.key[i] = 0;}
+            for &(code, key) in keymap.binds() {
+                if input.key_pressed(code) {input_delay.push(key as usize, true);}
+                else if input.key_released(code) {input_delay.push(key as usize, false);}
             }
+            input_delay.apply(&mut my_chip8.key);
             
+            // restart the ROM
+            if input.key_pressed(KeyCode::F5) {
+                my_chip8.reset();
+                my_chip8.memory[..0x200].copy_from_slice(&interpreter);
+                my_chip8.pc = entry;
+                window.request_redraw();
+            }
+
+            // pause and resume with P, also after a watchpoint or breakpoint,
+            // and run one instruction at a time with N while paused
+            if input.key_pressed(KeyCode::KeyP) {
+                if my_chip8.paused {
+                    my_chip8.resume();
+                } else {
+                    my_chip8.pause();
+                }
+            }
+            if my_chip8.paused && input.key_pressed(KeyCode::KeyN) {
+                let pc = my_chip8.pc;
+                let result = my_chip8.step_once();
+                println!("{}  {}", my_chip8.location(pc), disasm::text(&DecodedInstruction::new(my_chip8.opcode)));
+                if let Err(Chip8Error::UnknownOpcode(opcode)) = result {
+                    println!("Unknown opcode: {:#0X}", opcode);
+                }
+                headless::report_stop(&my_chip8);
+                window.request_redraw();
+            }
+
+            // scrub a replay a second at a time with the arrow keys
+            if let Some((timeline, frame)) = &mut replay {
+                let back = input.key_pressed(KeyCode::ArrowLeft);
+                if back || input.key_pressed(KeyCode::ArrowRight) {
+                    let target = if back {
+                        frame.saturating_sub(replay::KEYFRAME_INTERVAL)
+                    } else {
+                        *frame + replay::KEYFRAME_INTERVAL
+                    };
+                    *frame = timeline.seek(&mut my_chip8, target);
+                    my_chip8.draw_flag = true;
+                    overlay.show(frame.to_string());
+                    window.request_redraw();
+                }
+            }
+
+            // cycle the quirk presets with F7 and F8, for finding the one a
+            // misbehaving ROM needs; the overlay can only show digits, so it
+            // flashes the preset's number and the title names it
+            let previous = input.key_pressed(KeyCode::F7);
+            if previous || input.key_pressed(KeyCode::F8) {
+                preset = preset.cycle(if previous { -1 } else { 1 });
+                my_chip8.quirks = preset.quirks();
+                let number = Preset::ALL.iter().position(|&p| p == preset).unwrap() + 1;
+                overlay.show(number.to_string());
+                window.set_title(&format!("chip8 - {}", preset.name()));
+                if options.eco {
+                    window.request_redraw();
+                }
+            }
+
+            // nudge the clock speed with + and -
+            let faster = input.key_pressed(KeyCode::Equal) || input.key_pressed(KeyCode::NumpadAdd);
+            let slower = input.key_pressed(KeyCode::Minus) || input.key_pressed(KeyCode::NumpadSubtract);
+            if faster || slower {
+                my_chip8.clock_speed = timing::step_speed(my_chip8.clock_speed, if faster { 1 } else { -1 });
+                overlay.show(my_chip8.clock_speed.to_string());
+                if options.eco {
+                    window.request_redraw();
+                }
+            }
+
             // resize the window
             if let Some(size) = input.window_resized() {
                 my_chip8.draw_flag = true;
+                #[cfg(feature = "ui")]
+                if let Some(framework) = framework.as_mut() {
+                    framework.resize(size.width, size.height);
+                }
                 if let Err(err) = pixels.resize_surface(size.width, size.height) {
                     log_error("pixels.resize_surface", err);
                     elwt.exit();
@@ -143,12 +524,69 @@ fn main() -> Result<(), Error> {
                 }
             }
 
-            window.request_redraw();
+            // eco mode only redraws when the ROM has drawn something
+            if !options.eco {
+                window.request_redraw();
+            }
         }
     });
+
+    if let Some(recorder) = recorder {
+        if let Err(err) = recorder.finish() {
+            log_error("Recorder::finish", err);
+        }
+    }
+    if let Err(err) = rpl::save(&rpl_dir, my_chip8.rom_hash, &my_chip8.rpl) {
+        log_error("rpl::save", err);
+    }
+    if let (Some(session), Some(path)) = (&session, &options.record_input) {
+        if let Err(err) = session.save(std::path::Path::new(path)) {
+            log_error("Recording::save", err);
+        }
+    }
+    if options.stats {
+        headless::stats(&my_chip8);
+    }
     res.map_err(|e| Error::UserDefined(Box::new(e)))
 }
 
+fn tick_timers(my_chip8: &mut Chip8, recorder: &mut Option<audio::Recorder>, speaker: &mut Option<audio::Speaker>) {
+    // time stands still while paused, and the buzzer with it
+    if my_chip8.paused {
+        if let Some(speaker) = speaker {
+            speaker.set(Tone::Silent);
+        }
+        return;
+    }
+
+    // start or stop the buzzer for this frame
+    if let Some(speaker) = speaker {
+        speaker.set(if my_chip8.tone() { my_chip8.sound() } else { Tone::Silent });
+    }
+
+    // record this frame's audio before the sound timer moves on
+    if let Some(rec) = recorder {
+        if let Err(err) = rec.frame(my_chip8.sound()) {
+            log_error("Recorder::frame", err);
+            *recorder = None;
+        }
+    }
+
+    // timers driven by emulated cycles tick themselves
+    if !my_chip8.cycle_timers {
+        my_chip8.tick_timers();
+    }
+}
+
+// the pixel buffer for a display resolution: the display, and the memory
+// view below it, one pixel per byte at the display's width
+fn buffer_size((width, height): (u32, u32), mem_view: bool) -> (u32, u32) {
+    if mem_view {
+        (width, height + MEMORY_SIZE / width)
+    } else {
+        (width, height)
+    }
+}
 
 fn log_error<E: std::error::Error + 'static>(method_name: &str, err:E) {
     error!("{method_name}() faild: {err}");
@@ -0,0 +1,106 @@
+use std::io::{self, Read, Write};
+use std::net::{SocketAddr, TcpListener};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::events::Event;
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_metrics.rs"]
+mod test_metrics;
+
+// run counters served over HTTP in the Prometheus text format, for
+// monitoring long-running installs. the run loop updates them and a
+// background thread answers every request with the current values
+
+#[derive(Default)]
+pub struct Metrics {
+    pub instructions: AtomicU64,
+    pub frames:       AtomicU64,            // frames presented
+    fps_bits:         AtomicU64,            // f64 bits of the last measured frame rate
+    pub collisions:   AtomicU64,
+    pub beeps:        AtomicU64,            // times the sound started
+}
+
+impl Metrics {
+
+    pub fn event(&self, event: Event) {
+        match event {
+            Event::Collision { .. } => { self.collisions.fetch_add(1, Ordering::Relaxed); }
+            Event::SoundStart => { self.beeps.fetch_add(1, Ordering::Relaxed); }
+            _ => {}
+        }
+    }
+
+    pub fn set_fps(&self, fps: f64) {
+        self.fps_bits.store(fps.to_bits(), Ordering::Relaxed);
+    }
+
+    pub fn render(&self) -> String {
+        let counter = |name: &str, help: &str, value: &AtomicU64| {
+            format!("# HELP {0} {1}\n# TYPE {0} counter\n{0} {2}\n", name, help, value.load(Ordering::Relaxed))
+        };
+        let mut text = String::new();
+        text += &counter("chip8_instructions_total", "Instructions executed.", &self.instructions);
+        text += &counter("chip8_frames_total", "Frames presented.", &self.frames);
+        text += &format!(
+            "# HELP chip8_fps Frames presented per second.\n# TYPE chip8_fps gauge\nchip8_fps {}\n",
+            f64::from_bits(self.fps_bits.load(Ordering::Relaxed))
+        );
+        text += &counter("chip8_collisions_total", "Sprite draws that erased a pixel.", &self.collisions);
+        text += &counter("chip8_beeps_total", "Times the sound timer started.", &self.beeps);
+        text
+    }
+}
+
+// presented frames per second, measured over whole seconds
+pub struct FpsCounter {
+    start:  Instant,
+    frames: u32,
+}
+
+impl FpsCounter {
+
+    pub fn new(now: Instant) -> Self {
+        Self { start: now, frames: 0 }
+    }
+
+    // count a frame, returning the rate once a second has passed
+    pub fn frame(&mut self, now: Instant) -> Option<f64> {
+        self.frames += 1;
+        let elapsed = now.duration_since(self.start);
+        if elapsed < Duration::from_secs(1) {
+            return None;
+        }
+        let fps = self.frames as f64 / elapsed.as_secs_f64();
+        *self = Self::new(now);
+        Some(fps)
+    }
+}
+
+// answer every request on `port`, on all interfaces, from a background
+// thread; returns the address actually bound, for port 0
+pub fn serve(port: u16, metrics: Arc<Metrics>) -> io::Result<SocketAddr> {
+    let listener = TcpListener::bind(("0.0.0.0", port))?;
+    let addr = listener.local_addr()?;
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue,
+            };
+            // the request itself doesn't matter, only that it was sent
+            let mut request = [0; 1024];
+            let _ = stream.read(&mut request);
+            let body = metrics.render();
+            let _ = write!(
+                stream,
+                "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                body.len(), body
+            );
+        }
+    });
+    Ok(addr)
+}
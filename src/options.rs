@@ -0,0 +1,239 @@
+// command line options
+
+use clap::{ArgAction, Parser};
+use chip8::quirks::Preset;
+use chip8::watch::{self, Watch};
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_options.rs"]
+mod test_options;
+
+/// A CHIP-8, SUPER-CHIP and XO-CHIP emulator
+#[derive(Parser)]
+#[command(version)]
+pub struct Options {
+    /// ROM to run
+    #[arg(required_unless_present_any = ["memory_image", "diff_trace"], conflicts_with = "memory_image")]
+    pub rom:        Option<String>,
+
+    /// Screen pixels per CHIP-8 pixel
+    #[arg(long, value_name = "N", value_parser = scale, help_heading = "Display")]
+    pub scale:      Option<u32>,
+    /// Start in borderless fullscreen
+    #[arg(long, help_heading = "Display")]
+    pub fullscreen: bool,
+    /// Lit pixel colour
+    #[arg(long, value_name = "RRGGBB", value_parser = color, default_value = "ffffff", help_heading = "Display")]
+    pub fg:         [u8; 4],
+    /// Unlit pixel colour
+    #[arg(long, value_name = "RRGGBB", value_parser = color, default_value = "000000", help_heading = "Display")]
+    pub bg:         [u8; 4],
+    /// Sync presentation to the display
+    #[arg(long, value_name = "on|off", value_parser = switch, default_value = "on", action = ArgAction::Set, help_heading = "Display")]
+    pub vsync:      bool,
+    /// Present at most one frame per this many milliseconds
+    #[arg(long, value_name = "MS", default_value_t = 0, help_heading = "Display")]
+    pub min_frame_ms: u64,
+    /// Show memory below the display
+    #[arg(long, help_heading = "Display")]
+    pub mem_view:   bool,
+    /// Settings panel (ui feature)
+    #[arg(long, help_heading = "Display")]
+    pub ui:         bool,
+
+    /// Instructions per 60Hz frame
+    #[arg(long, value_name = "N", value_parser = positive, help_heading = "Timing and input")]
+    pub ipf:        Option<u32>,
+    /// Sleep between ticks to save power
+    #[arg(long, help_heading = "Timing and input")]
+    pub eco:        bool,
+    /// Stop running while the ROM waits on a key
+    #[arg(long, help_heading = "Timing and input")]
+    pub halt_idle:  bool,
+    /// Tick timers from emulated time
+    #[arg(long, help_heading = "Timing and input")]
+    pub cycle_timers: bool,
+    /// Delay key presses by this many frames
+    #[arg(long, value_name = "FRAMES", default_value_t = 0, help_heading = "Timing and input")]
+    pub input_delay: u64,
+    /// Key bindings, one `Key = hex` per line
+    #[arg(long, value_name = "FILE", help_heading = "Timing and input")]
+    pub keymap:     Option<String>,
+    /// No sound
+    #[arg(long, help_heading = "Timing and input")]
+    pub mute:       bool,
+    /// Write the buzzer to a WAV file
+    #[arg(long, value_name = "WAV", help_heading = "Timing and input")]
+    pub record_audio: Option<String>,
+
+    /// Quirk preset: cosmac, schip, xochip or modern
+    #[arg(long, value_name = "PRESET", value_parser = preset, default_value = "modern", help_heading = "Machine")]
+    pub quirks:     Preset,
+    /// Leave the SUPER-CHIP font out
+    #[arg(long = "no-large-font", action = ArgAction::SetFalse, help_heading = "Machine")]
+    pub large_font: bool,
+    /// COSMAC VIP interpreter image at 0x000
+    #[arg(long, value_name = "FILE", help_heading = "Machine")]
+    pub vip_rom:    Option<String>,
+    /// Full 4K memory image, run instead of a ROM
+    #[arg(long, value_name = "FILE", help_heading = "Machine")]
+    pub memory_image: Option<String>,
+    /// Where a memory image starts running
+    #[arg(long, value_name = "ADDR", value_parser = address, default_value = "0x200", help_heading = "Machine")]
+    pub entry:      u16,
+    /// Bytes before the program in the ROM file
+    #[arg(long, value_name = "N", default_value_t = 0, help_heading = "Machine")]
+    pub skip_header: usize,
+    /// Bytes after the program in the ROM file
+    #[arg(long, value_name = "N", default_value_t = 0, help_heading = "Machine")]
+    pub skip_trailer: usize,
+    /// Routine run once per frame at 0xE00
+    #[arg(long, value_name = "FILE", help_heading = "Machine")]
+    pub trainer:    Option<String>,
+    /// Random seed
+    #[arg(long, default_value_t = 0, help_heading = "Machine")]
+    pub seed:       u64,
+    /// Check the fontset after loading
+    #[arg(long, help_heading = "Machine")]
+    pub verify:     bool,
+
+    /// Pause before the instruction at this address
+    #[arg(long = "break", value_name = "ADDR", value_parser = address, help_heading = "Debugging")]
+    pub breakpoints: Vec<u16>,
+    /// Pause when a register or memory byte is reached: VX==n or ADDR==n
+    #[arg(long = "break-reg", visible_alias = "break-mem", value_name = "WATCH", value_parser = Watch::parse, help_heading = "Debugging")]
+    pub watchpoints: Vec<Watch>,
+    /// Octo source lines for addresses
+    #[arg(long, value_name = "FILE", help_heading = "Debugging")]
+    pub octo_map:   Option<String>,
+    /// Log every nth instruction (RUST_LOG=trace)
+    #[arg(long, value_name = "N", default_value_t = 1, help_heading = "Debugging")]
+    pub trace_every: u64,
+    /// Warn when nothing is drawn for this many frames
+    #[arg(long, value_name = "FRAMES", default_value_t = 600, help_heading = "Debugging")]
+    pub draw_timeout: u32,
+    /// Warn when the ROM writes over the last sprite drawn
+    #[arg(long, help_heading = "Debugging")]
+    pub warn_sprite_write: bool,
+    /// Warn when pc is odd at fetch
+    #[arg(long, help_heading = "Debugging")]
+    pub warn_misalign: bool,
+    /// Stop with an error when pc is odd at fetch
+    #[arg(long, help_heading = "Debugging")]
+    pub strict_align: bool,
+    /// Run statistics on exit
+    #[arg(long, help_heading = "Debugging")]
+    pub stats:      bool,
+    /// Serve metrics on this port (metrics feature)
+    #[arg(long, value_name = "PORT", help_heading = "Debugging")]
+    pub metrics_port: Option<u16>,
+    /// Record the keys held in every frame
+    #[arg(long, value_name = "FILE", help_heading = "Debugging")]
+    pub record_input: Option<String>,
+    /// Replay recorded key presses
+    #[arg(long, value_name = "FILE", help_heading = "Debugging")]
+    pub replay:     Option<String>,
+
+    /// Instructions to run
+    #[arg(long, value_name = "N", default_value_t = 10_000, help_heading = "Headless")]
+    pub cycles:     usize,
+    /// Print registers and a display hash
+    #[arg(long, help_heading = "Headless")]
+    pub canonical_dump: bool,
+    /// Save the display as SVG
+    #[arg(long, value_name = "FILE", help_heading = "Headless")]
+    pub svg:        Option<String>,
+    /// SVG pixels per CHIP-8 pixel
+    #[arg(long, value_name = "N", default_value_t = 10, help_heading = "Headless")]
+    pub svg_scale:  u32,
+    /// Print the display as text
+    #[arg(long, help_heading = "Headless")]
+    pub text_out:   bool,
+    /// Characters for lit and unlit pixels in --text-out
+    #[arg(long, value_name = "CHARS", value_parser = chars, default_value = "#.", help_heading = "Headless")]
+    pub text_chars: (char, char),
+    /// First difference between two ROMs' traces
+    #[arg(long, num_args = 2, value_names = ["A", "B"], help_heading = "Headless")]
+    pub diff_trace: Option<Vec<String>>,
+    /// Write a display hash per frame
+    #[arg(long, value_name = "FILE", help_heading = "Headless")]
+    pub record_frames: Option<String>,
+    /// Check display hashes against a recording
+    #[arg(long, value_name = "FILE", help_heading = "Headless")]
+    pub verify_frames: Option<String>,
+    /// Generate opcode tests from a run
+    #[arg(long, value_name = "FILE", help_heading = "Headless")]
+    pub gen_tests:  Option<String>,
+    /// Print the disassembly and exit
+    #[arg(long, help_heading = "Headless")]
+    pub disasm:     bool,
+    /// Print the call graph as DOT
+    #[arg(long, help_heading = "Headless")]
+    pub callgraph:  bool,
+    /// Instruction counts as CSV
+    #[arg(long, value_name = "FILE", help_heading = "Headless")]
+    pub profile_csv: Option<String>,
+    /// Read the quirks test ROM's results
+    #[arg(long = "run-quirks-test", help_heading = "Headless")]
+    pub quirks_test: bool,
+}
+
+fn switch(text: &str) -> Result<bool, String> {
+    match text {
+        "on"  => Ok(true),
+        "off" => Ok(false),
+        _ => Err("expected on or off".to_string()),
+    }
+}
+
+fn chars(text: &str) -> Result<(char, char), String> {
+    let mut chars = text.chars();
+    match (chars.next(), chars.next(), chars.next()) {
+        (Some(on), Some(off), None) => Ok((on, off)),
+        _ => Err("expected two characters".to_string()),
+    }
+}
+
+fn preset(text: &str) -> Result<Preset, String> {
+    Preset::ALL.iter()
+        .copied()
+        .find(|preset| preset.name() == text)
+        .ok_or_else(|| "expected cosmac, schip, xochip or modern".to_string())
+}
+
+// a whole number, 1 or more
+fn positive(text: &str) -> Result<u32, String> {
+    match text.parse() {
+        Ok(n) if n >= 1 => Ok(n),
+        _ => Err("expected a whole number, 1 or more".to_string()),
+    }
+}
+
+// screen pixels per CHIP-8 pixel, bounded so the window size can't overflow;
+// 64 already makes the plain display 4096 pixels wide
+const MAX_SCALE: u32 = 64;
+
+fn scale(text: &str) -> Result<u32, String> {
+    match positive(text)? {
+        n if n <= MAX_SCALE => Ok(n),
+        _ => Err(format!("expected at most {}", MAX_SCALE)),
+    }
+}
+
+// an opaque colour as RRGGBB hex, with or without a leading #
+fn color(text: &str) -> Result<[u8; 4], String> {
+    let hex = text.strip_prefix('#').unwrap_or(text);
+    match u32::from_str_radix(hex, 16) {
+        Ok(rgb) if hex.len() == 6 => Ok([(rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 0xff]),
+        _ => Err("expected a colour as RRGGBB".to_string()),
+    }
+}
+
+// a memory address, decimal or 0x hex
+fn address(text: &str) -> Result<u16, String> {
+    match watch::parse_number(text) {
+        Some(addr) if addr < 0x1000 => Ok(addr as u16),
+        _ => Err("expected an address below 0x1000".to_string()),
+    }
+}
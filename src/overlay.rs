@@ -0,0 +1,65 @@
+use std::time::{Duration, Instant};
+use chip8::processor::{nibble_to_font_addr, FONTSET};
+
+// how long a message stays on screen
+pub const OVERLAY_DURATION: Duration = Duration::from_secs(1);
+
+const COLOR: [u8; 4] = [0xff, 0xcc, 0x00, 0xff];
+
+// short status text drawn over the top-left of the display with the built-in
+// font, so only hex digits can be shown
+
+pub struct Overlay {
+    text:  Option<String>,
+    until: Instant,
+}
+
+impl Overlay {
+
+    pub fn new() -> Self {
+        Self {
+            text:  None,
+            until: Instant::now(),
+        }
+    }
+
+    pub fn show(&mut self, text: String) {
+        self.text = Some(text);
+        self.until = Instant::now() + OVERLAY_DURATION;
+    }
+
+    // true while a message is up, including the redraw that clears it
+    pub fn needs_redraw(&self) -> bool {
+        self.text.is_some()
+    }
+
+    // `frame` is RGBA, `width` pixels to a row
+    pub fn draw(&mut self, frame: &mut [u8], width: usize) {
+        if Instant::now() >= self.until {
+            self.text = None;
+        }
+        let text = match &self.text {
+            Some(text) => text,
+            None => return,
+        };
+
+        for (n, digit) in text.chars().filter_map(|c| c.to_digit(16)).enumerate() {
+            let glyph = nibble_to_font_addr(digit as u8) as usize;
+            for row in 0..5 {
+                for col in 0..4 {
+                    if FONTSET[glyph + row] & (0x80 >> col) == 0 {
+                        continue;
+                    }
+                    // one pixel margin around the text, one pixel between glyphs
+                    let x = 1 + n * 5 + col;
+                    let y = 1 + row;
+                    if x >= width {
+                        return;
+                    }
+                    let i = (y * width + x) * 4;
+                    frame[i..i + 4].copy_from_slice(&COLOR);
+                }
+            }
+        }
+    }
+}
@@ -1,32 +1,374 @@
-use rand::Rng;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha12Rng;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::fmt;
 use std::fs;
+use std::ops::Range;
 use std::path::Path;
-use crate::{WIDTH};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::time::Duration;
+use crate::{WIDTH, HEIGHT, HIRES_WIDTH, HIRES_HEIGHT, TICK_SPEED};
+use crate::audio::{self, Tone};
+use crate::events::Event;
+use crate::instruction::DecodedInstruction;
+use crate::quirks::Quirks;
+#[cfg(feature = "serde")]
+use crate::savestate;
+use crate::srcmap::SourceMap;
+use crate::trace::TraceEntry;
+use crate::watch::Watch;
+
+// the delay and sound timers count down at 60Hz
+pub const TIMER_PERIOD: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
+// trainer ROMs live in this reserved block of high memory and are called
+// once per 60Hz frame like a subroutine, ending with RET
+pub const TRAINER_ADDR: u16 = 0xE00;
+pub const TRAINER_SIZE: usize = 0x100;
+// most instructions a trainer may run in one frame
+const TRAINER_BUDGET: usize = 1000;
+
+// average instructions per display update above which a ROM probably runs
+// faster than it was written for
+pub const FAST_DRAW_THRESHOLD: f64 = 1000.0;
+
+// XO-CHIP display planes; each pixel in gfx holds one bit per plane
+pub const PLANES: usize = 2;
+
+// instructions kept for Chip8::trace by default
+pub const TRACE_LEN: usize = 256;
+
+// custom instruction handler, given the machine and the raw opcode; it is
+// responsible for advancing pc
+pub type OpcodeHandler = fn(&mut Chip8, u16);
+
+// problems running an instruction. the misaligned pc and stack faults stop
+// the interpreter; the others are reported by step and execution carries on
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Chip8Error {
+    MisalignedPc(u16),                      // fetch from an odd address with --strict-align
+    StackOverflow,                          // CALL with all 16 stack entries in use
+    StackUnderflow,                         // RET with an empty stack
+    UnknownOpcode(u16),                     // skipped without advancing pc
+    MemoryOutOfBounds(u16),                 // access past 0xFFF, wrapped to the bottom of memory
+    Load(LoadError),                        // a program that can't be loaded
+    // a save state that can't be restored. never stored in a running
+    // machine, so a save state keeps the variant but not the reason
+    BadState(#[cfg_attr(feature = "serde", serde(skip))] &'static str),
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Chip8Error::MisalignedPc(pc) => write!(f, "instruction fetch from odd address {:#05x}", pc),
+            Chip8Error::StackOverflow => write!(f, "stack overflow, more than 16 nested calls"),
+            Chip8Error::StackUnderflow => write!(f, "return with an empty stack"),
+            Chip8Error::UnknownOpcode(opcode) => write!(f, "unknown opcode {:#06x}", opcode),
+            Chip8Error::MemoryOutOfBounds(addr) => write!(f, "memory access at {:#06x}, past the end of memory", addr),
+            Chip8Error::Load(err) => write!(f, "{}", err),
+            Chip8Error::BadState(reason) => write!(f, "invalid save state: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Chip8Error {}
+
+// reasons a ROM file can't be loaded, besides failing to read it
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LoadError {
+    TooLarge { size: usize, max: usize },   // more than fits from 0x200 up
+    SkipTooLong { skipped: usize, size: usize }, // --skip-header/--skip-trailer past the end
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::TooLarge { size, max } => write!(f, "program is {} bytes, memory holds {}", size, max),
+            LoadError::SkipTooLong { skipped, size } => write!(f, "cannot skip {} bytes of a {} byte file", skipped, size),
+        }
+    }
+}
+
+impl std::error::Error for LoadError {}
+
+impl From<LoadError> for Chip8Error {
+    fn from(err: LoadError) -> Self {
+        Chip8Error::Load(err)
+    }
+}
+
+// display colours, RGBA. a background alpha below 0xff leaves unlit pixels
+// see-through so the output can be composited over other content. XO-CHIP
+// ROMs drawing on the second plane use the other two
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Palette {
+    pub foreground: [u8; 4],                // lit on the first plane
+    pub background: [u8; 4],
+    pub second:     [u8; 4],                // lit on the second plane only
+    pub blend:      [u8; 4],                // lit on both planes
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            foreground: [0xff, 0xff, 0xff, 0xff],
+            background: [0x00, 0x00, 0x00, 0xff],
+            second:     [0xaa, 0xaa, 0xaa, 0xff],
+            blend:      [0x55, 0x55, 0x55, 0xff],
+        }
+    }
+}
+
+impl Palette {
+    // the colour of a pixel, given its plane bits
+    pub fn color(&self, pixel: u8) -> [u8; 4] {
+        match pixel & 0b11 {
+            0 => self.background,
+            1 => self.foreground,
+            2 => self.second,
+            _ => self.blend,
+        }
+    }
+}
+
+// #rrggbb, or #rrggbbaa when not fully opaque
+fn css_color(rgba: [u8; 4]) -> String {
+    if rgba[3] == 0xff {
+        format!("#{:02x}{:02x}{:02x}", rgba[0], rgba[1], rgba[2])
+    } else {
+        format!("#{:02x}{:02x}{:02x}{:02x}", rgba[0], rgba[1], rgba[2], rgba[3])
+    }
+}
+
+// hex digit glyphs 0-F, 4x5 pixels each, loaded at 0x000
+pub const FONTSET: [u8; 80] = [
+    0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
+    0x20, 0x60, 0x20, 0x20, 0x70, // 1
+    0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
+    0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
+    0x90, 0x90, 0xF0, 0x10, 0x10, // 4
+    0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
+    0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
+    0xF0, 0x10, 0x20, 0x40, 0x40, // 7
+    0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
+    0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
+    0xF0, 0x90, 0xF0, 0x90, 0x90, // A
+    0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
+    0xF0, 0x80, 0x80, 0x80, 0xF0, // C
+    0xE0, 0x90, 0x90, 0x90, 0xE0, // D
+    0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
+    0xF0, 0x80, 0xF0, 0x80, 0x80  // F
+];
+
+// SUPER-CHIP large digit glyphs 0-9, 8x10 pixels each, loaded after the
+// small font
+pub const BIG_FONT_ADDR: u16 = 0x50;
+pub const BIG_FONTSET: [u8; 100] = [
+    0x3C, 0x7E, 0xE7, 0xC3, 0xC3, 0xC3, 0xC3, 0xE7, 0x7E, 0x3C, // 0
+    0x18, 0x38, 0x58, 0x18, 0x18, 0x18, 0x18, 0x18, 0x18, 0x3C, // 1
+    0x3E, 0x7F, 0xC3, 0x06, 0x0C, 0x18, 0x30, 0x60, 0xFF, 0xFF, // 2
+    0x3C, 0x7E, 0xC3, 0x03, 0x0E, 0x0E, 0x03, 0xC3, 0x7E, 0x3C, // 3
+    0x06, 0x0E, 0x1E, 0x36, 0x66, 0xC6, 0xFF, 0xFF, 0x06, 0x06, // 4
+    0xFF, 0xFF, 0xC0, 0xC0, 0xFC, 0xFE, 0x03, 0xC3, 0x7E, 0x3C, // 5
+    0x3E, 0x7C, 0xC0, 0xC0, 0xFC, 0xFE, 0xC3, 0xC3, 0x7E, 0x3C, // 6
+    0xFF, 0xFF, 0x03, 0x06, 0x0C, 0x18, 0x30, 0x60, 0x60, 0x60, // 7
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7E, 0x7E, 0xC3, 0xC3, 0x7E, 0x3C, // 8
+    0x3C, 0x7E, 0xC3, 0xC3, 0x7F, 0x3F, 0x03, 0x03, 0x3E, 0x7C  // 9
+];
 
 // configure test cases
 #[cfg(test)]
 #[path = "test_opcodes.rs"]
 mod test_opcodes;
+#[cfg(test)]
+#[path = "test_no_panic.rs"]
+mod test_no_panic;
+
+// helpers
+
+// address of the built-in font glyph for a hex digit
+pub fn nibble_to_font_addr(nibble: u8) -> u16 {
+    (nibble & 0x0F) as u16 * 5
+}
+
+// hundreds, tens, and ones digits of a byte
+pub fn byte_to_bcd(b: u8) -> [u8; 3] {
+    [b / 100, (b / 10) % 10, b % 10]
+}
+
+// 64-bit FNV-1a, a small hash that is stable across platforms and releases
+pub fn fnv1a<I: IntoIterator<Item = u8>>(data: I) -> u64 {
+    data.into_iter().fold(0xcbf29ce484222325, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    })
+}
 
 // implement data types
 
+
+// the random source's position in its sequence: the ChaCha key, the stream
+// and the word position within it
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RngState {
+    pub seed:     [u8; 32],
+    pub stream:   u64,
+    pub word_pos: u128,
+}
+
+impl RngState {
+
+    pub(crate) fn of(rng: &ChaCha12Rng) -> Self {
+        Self { seed: rng.get_seed(), stream: rng.get_stream(), word_pos: rng.get_word_pos() }
+    }
+
+    pub(crate) fn rng(&self) -> ChaCha12Rng {
+        let mut rng = ChaCha12Rng::from_seed(self.seed);
+        rng.set_stream(self.stream);
+        rng.set_word_pos(self.word_pos);
+        rng
+    }
+}
+
+// the machine state that execution depends on, for restoring a run to an
+// earlier point. settings, collectors and hooks are not included
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Snapshot {
+    pub(crate) opcode:        u16,
+    #[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]
+    pub(crate) memory:        [u8; 4096],
+    pub(crate) v:             [u8; 16],
+    pub(crate) i:             u16,
+    pub(crate) pc:            u16,
+    #[cfg_attr(feature = "serde", serde(with = "crate::savestate::gfx"))]
+    pub(crate) gfx:           [[u8; 128]; 64],
+    pub(crate) hires:         bool,
+    pub(crate) selected_planes: u8,
+    pub(crate) delay_timer:   u8,
+    pub(crate) sound_timer:   u8,
+    pub(crate) audio_buffer:  [u8; 16],
+    pub(crate) pitch:         u8,
+    pub(crate) pattern_audio: bool,
+    pub(crate) stack:         [u16; 16],
+    pub(crate) sp:            usize,
+    pub(crate) key:           [bool; 16],
+    pub(crate) draw_flag:     bool,
+    pub(crate) halted:        bool,
+    pub(crate) exited:        bool,
+    pub(crate) error:         Option<Chip8Error>,
+    pub(crate) timer_elapsed: Duration,
+    pub(crate) rng:           ChaCha12Rng,
+    pub(crate) rpl:           [u8; 8],
+}
+
+// the registers a debugger shows, copied out so a frontend can hold on to
+// them without borrowing the machine
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct MachineSnapshot {
+    pub pc:          u16,
+    pub i:           u16,
+    pub sp:          usize,
+    pub v:           [u8; 16],
+    pub delay_timer: u8,
+    pub sound_timer: u8,
+    pub opcode:      u16,                   // last instruction fetched
+}
+
 pub struct Chip8 {
     pub opcode:      u16,                   // unsigned short opcode;
     pub memory:      [u8; 4096],            // unsigned char memory[4096];
     pub v:           [u8; 16],              // unsigned char V[16];
     pub i:           u16,                   // unsigned short I;
     pub pc:          u16,                   // unsigned short pc;
-    pub gfx:         [[u8; 32]; 64],        // unsigned char gfx[64 * 32];
+    pub gfx:         [[u8; 128]; 64],       // rows of pixels, gfx[y][x]; only the top-left 64x32 outside hi-res mode
+    pub hires:       bool,                  // SUPER-CHIP 128x64 display, 00FF on and 00FE off
+    pub selected_planes: u8,                // XO-CHIP planes drawn on, bit 0 the first; set by FN01
     pub delay_timer: u8,                    // unsigned char delay_timer;
     pub sound_timer: u8,                    // unsigned char sound_timer;
+    pub audio_buffer: [u8; 16],             // XO-CHIP sound pattern, loaded by F002
+    pub pitch:       u8,                    // XO-CHIP pattern playback rate, set by Fx3A
+    pub pattern_audio: bool,                // play audio_buffer instead of the buzzer
     pub stack:       [u16; 16],             // unsigned short stack[16];
     pub sp:          usize,                 // unsigned short sp;
-    pub key:         [u8; 16],              // unsigned char key[16];
+    pub max_sp:      usize,                 // deepest stack nesting reached
+    pub call_edges:  Option<BTreeSet<(u16, u16)>>, // (call site, target) pairs, when collecting
+    pub profile:     Option<BTreeMap<(&'static str, &'static str), u64>>, // (class, mnemonic) counts, when profiling
+    pub key:         [bool; 16],            // pressed state of keys 0-F
+    pub palette:     Palette,
     pub draw_flag:   bool,
+    pub frames_since_draw: u32,             // 60Hz frames since the ROM last drew
+    pub draw_timeout: u32,                  // warn after this many frames without drawing, 0 = never
+    pub instructions: u64,                  // instructions executed since power-on
+    pub instructions_since_draw: u64,       // instructions since the last Dxyn or 00E0
+    draw_intervals:  (u64, u64),            // (display updates, instructions between them)
+    pub warn_sprite_write: bool,            // warn on writes to the last sprite drawn
+    pub sprite_source: Option<(u16, u16)>,  // address and length of the last sprite drawn
+    pub last_warning: Option<String>,
+    pub trace_every: u64,                   // log every nth instruction at trace level
+    pub trace_count: u64,                   // instructions since the last one logged
+    pub trace_lines: u64,                   // instructions logged so far
+    pub trace_len:   usize,                 // instructions kept for trace(), 0 for none
+    history:         Vec<TraceEntry>,       // recent instructions, oldest first
+    pub halted:      bool,                  // spinning on a jump to itself
+    pub exited:      bool,                  // interpreter stopped by 00FD
+    pub error:       Option<Chip8Error>,    // interpreter stopped by a fault
+    pub warn_misalign: bool,                // warn when pc is odd at fetch
+    pub strict_align: bool,                 // stop with an error when pc is odd at fetch
+    pub cycle_timers: bool,                 // tick timers from emulated time, not wall time
+    pub timer_elapsed: Duration,            // emulated time since the last timer tick
+    pub rng:         ChaCha12Rng,           // StdRng's generator, with its state exposed
+    pub clock_speed: u64,                   // instructions per second
+    pub rpl:         [u8; 8],               // SCHIP RPL user flags
+    pub large_font:  bool,                  // BIG_FONTSET is loaded for Fx30
+    audio_enabled:   bool,                  // frontends may play the buzzer
+    pub rom_hash:    u64,                   // FNV-1a of the loaded ROM
+    overrides:       Vec<(u16, u16, OpcodeHandler)>, // (mask, value, handler)
+    events:          Option<Sender<Event>>,
+    trainer:         bool,                  // a trainer is loaded at TRAINER_ADDR
+    pub paused:      bool,                  // stopped by a watchpoint or pause()
+    pub skip_header: usize,                 // bytes before the program in ROM files
+    pub skip_trailer: usize,                // bytes after the program in ROM files
+    watchpoints:     Vec<(Watch, bool)>,    // (watch, held after the last cycle)
+    pub watch_hit:   Option<Watch>,         // the watchpoint that paused the machine, if any
+    breakpoints:     HashSet<u16>,          // pause before running these addresses
+    pub breakpoint_hit: Option<u16>,        // paused at this breakpoint, not yet run
+    pub source_map:  Option<SourceMap>,     // Octo source lines by address
+    pub quirks:      Quirks,                // interpreter-specific behaviours
+}
+
+// registers, timers and the stack in use, for printing with {:?}. memory
+// is left out; dump_memory shows part of it
+impl fmt::Debug for Chip8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "PC {:03X}  I {:03X}  SP {:X}  DT {:02X}  ST {:02X}  opcode {:04X}",
+            self.pc, self.i, self.sp, self.delay_timer, self.sound_timer, self.opcode)?;
+        for (half, regs) in self.v.chunks(8).enumerate() {
+            for (n, reg) in regs.iter().enumerate() {
+                write!(f, "V{:X} {:02X}{}", half * 8 + n, reg, if n < 7 { "  " } else { "\n" })?;
+            }
+        }
+        write!(f, "stack")?;
+        if self.sp == 0 {
+            write!(f, " empty")?;
+        }
+        for addr in &self.stack[..self.sp.min(self.stack.len())] {
+            write!(f, " {:03X}", addr)?;
+        }
+        writeln!(f)
+    }
 }
 
 impl Chip8 {
     
+    // a fresh machine with `quirks` instead of the modern defaults
+    pub fn with_quirks(quirks: Quirks) -> Self {
+        let mut my_chip8 = Self::initialize();
+        my_chip8.quirks = quirks;
+        my_chip8
+    }
+
     // create a new Chip8 instance
     pub fn initialize() -> Self {
         Self {
@@ -35,98 +377,765 @@ impl Chip8 {
             v:           [0; 16],          // clear registers V0-VF
             i:           0,                // reset index register
             pc:          0x200,            // program counter starts at 0x200
-            gfx:         [[0x00; 32]; 64], // clear display
+            gfx:         [[0x00; 128]; 64], // clear display
+            hires:       false,            // 64x32 display
+            selected_planes: 1,            // first plane only
             delay_timer: 0,                // reset delay timer
             sound_timer: 0,                // reset sound timer
+            audio_buffer: [0; 16],         // no pattern loaded
+            pitch:       audio::DEFAULT_PITCH,
+            pattern_audio: false,          // plain buzzer
             stack:       [0; 16],          // clear stack
             sp:          0,                // reset stack pointer
-            key:         [0; 16],          // assign keys
+            max_sp:      0,                // no calls yet
+            call_edges:  None,             // not collecting calls
+            profile:     None,             // not profiling
+            key:         [false; 16],      // assign keys
+            palette:     Palette::default(), // white on black
             draw_flag:   false,            // not ready to draw
+            frames_since_draw: 0,
+            draw_timeout: 0,               // no-draw warning off
+            instructions: 0,
+            instructions_since_draw: 0,
+            draw_intervals: (0, 0),
+            warn_sprite_write: false,
+            sprite_source: None,           // nothing drawn yet
+            last_warning: None,
+            trace_every: 1,                // log every instruction
+            trace_count: 0,
+            trace_lines: 0,
+            trace_len:   TRACE_LEN,
+            history:     Vec::new(),
+            halted:      false,            // running
+            exited:      false,            // not exited
+            error:       None,             // no fault
+            warn_misalign: false,
+            strict_align: false,
+            cycle_timers: false,           // timers ticked by the frontend
+            timer_elapsed: Duration::ZERO,
+            rng:         ChaCha12Rng::from_entropy(), // random source for Cxkk
+            clock_speed: TICK_SPEED,
+            rpl:         [0; 8],           // clear RPL flags
+            large_font:  false,            // small font only
+            audio_enabled: true,
+            rom_hash:    0,                // no ROM loaded
+            overrides:   Vec::new(),       // built-in instructions only
+            events:      None,             // nobody listening
+            trainer:     false,            // no trainer
+            paused:      false,            // running
+            skip_header: 0,                // plain ROM files
+            skip_trailer: 0,
+            watchpoints: Vec::new(),       // nothing watched
+            watch_hit:   None,
+            breakpoints: HashSet::new(),   // no breakpoints
+            breakpoint_hit: None,
+            source_map:  None,             // no source
+            quirks:      Quirks::default(), // modern behaviour
+        }
+    }
+
+    // start the loaded program over: registers, stack, timers, display and
+    // keys as after initialize, the fonts reloaded, and the rest of memory
+    // left as it is. settings, breakpoints and collectors are kept
+    pub fn reset(&mut self) {
+        self.opcode = 0;
+        self.v = [0; 16];
+        self.i = 0;
+        self.pc = 0x200;
+        self.gfx = [[0x00; 128]; 64];
+        self.hires = false;
+        self.selected_planes = 1;
+        self.delay_timer = 0;
+        self.sound_timer = 0;
+        self.audio_buffer = [0; 16];
+        self.pitch = audio::DEFAULT_PITCH;
+        self.pattern_audio = false;
+        self.stack = [0; 16];
+        self.sp = 0;
+        self.key = [false; 16];
+        self.draw_flag = true;              // show the cleared display
+        self.frames_since_draw = 0;
+        self.instructions_since_draw = 0;
+        self.sprite_source = None;
+        self.halted = false;
+        self.exited = false;
+        self.error = None;
+        self.paused = false;
+        self.breakpoint_hit = None;
+        self.watch_hit = None;
+        self.timer_elapsed = Duration::ZERO;
+        self.load_fontset();
+        if self.large_font {
+            self.load_large_font();
         }
     }
+
+    // reseed the random source so runs are reproducible
+    pub fn seed(&mut self, seed: u64) {
+        self.rng = ChaCha12Rng::seed_from_u64(seed);
+    }
+
+    // where the random source is in its sequence, to note at a breakpoint
+    pub fn rng_state(&self) -> RngState {
+        RngState::of(&self.rng)
+    }
+
+    // continue the random sequence from a noted state, so Cxkk draws the
+    // same values it did after that point
+    pub fn set_rng_state(&mut self, state: &RngState) {
+        self.rng = state.rng();
+    }
      
     pub fn load_fontset(&mut self) {
-        let fontset: [u8; 80] = [
-            0xF0, 0x90, 0x90, 0x90, 0xF0, // 0
-            0x20, 0x60, 0x20, 0x20, 0x70, // 1
-            0xF0, 0x10, 0xF0, 0x80, 0xF0, // 2
-            0xF0, 0x10, 0xF0, 0x10, 0xF0, // 3
-            0x90, 0x90, 0xF0, 0x10, 0x10, // 4
-            0xF0, 0x80, 0xF0, 0x10, 0xF0, // 5
-            0xF0, 0x80, 0xF0, 0x90, 0xF0, // 6
-            0xF0, 0x10, 0x20, 0x40, 0x40, // 7
-            0xF0, 0x90, 0xF0, 0x90, 0xF0, // 8
-            0xF0, 0x90, 0xF0, 0x10, 0xF0, // 9
-            0xF0, 0x90, 0xF0, 0x90, 0x90, // A
-            0xE0, 0x90, 0xE0, 0x90, 0xE0, // B
-            0xF0, 0x80, 0x80, 0x80, 0xF0, // C
-            0xE0, 0x90, 0x90, 0x90, 0xE0, // D
-            0xF0, 0x80, 0xF0, 0x80, 0xF0, // E
-            0xF0, 0x80, 0xF0, 0x80, 0x80  // F
-        ];
-
         for i in 0..80 {
-            self.memory[i] = fontset[i];
+            self.memory[i] = FONTSET[i];
         }
     }
 
+    // whether a frontend should play the buzzer; headless and test runs
+    // turn it off. the sound timer runs either way
+    pub fn set_audio_enabled(&mut self, enabled: bool) {
+        self.audio_enabled = enabled;
+    }
+
+    // the buzzer should be sounding right now
+    pub fn tone(&self) -> bool {
+        self.audio_enabled && self.sound_timer > 0
+    }
+
+    // what the sound timer is playing, whether or not the frontend plays it:
+    // the buzzer, or once an XO-CHIP ROM has loaded one, its pattern
+    pub fn sound(&self) -> Tone {
+        if self.sound_timer == 0 {
+            Tone::Silent
+        } else if self.pattern_audio {
+            Tone::Pattern { bits: self.audio_buffer, rate: audio::pattern_rate(self.pitch) }
+        } else {
+            Tone::Buzzer
+        }
+    }
+
+    pub fn load_large_font(&mut self) {
+        let start = BIG_FONT_ADDR as usize;
+        self.memory[start..start + BIG_FONTSET.len()].copy_from_slice(&BIG_FONTSET);
+        self.large_font = true;
+    }
+
+    // the font glyphs are still where Fx29 expects them
+    pub fn verify_fontset(&self) -> bool {
+        self.memory[..FONTSET.len()] == FONTSET
+    }
+
+    pub fn load_vip_rom(&mut self, data: &[u8]) -> Result<(), Box<dyn std::error::Error + 'static>> {
+        // load a COSMAC VIP interpreter image into the interpreter region
+        // (0x000-0x1FF) for ROMs that call or read its routines. this
+        // replaces the built-in fonts
+        if data.len() > 0x200 {
+            return Err(format!("VIP image is {} bytes, the interpreter region holds 512", data.len()).into());
+        }
+        self.memory[..data.len()].copy_from_slice(data);
+        self.large_font = false;
+        Ok(())
+    }
+
+    pub fn load_memory_image(&mut self, data: &[u8]) -> Result<(), Box<dyn std::error::Error + 'static>> {
+        // a full 4K snapshot, e.g. from another emulator, copied over the
+        // whole address space including the interpreter region
+        if data.len() != self.memory.len() {
+            return Err(format!("memory image is {} bytes, expected exactly {}", data.len(), self.memory.len()).into());
+        }
+        self.memory.copy_from_slice(data);
+        self.rom_hash = fnv1a(data.iter().copied());
+        Ok(())
+    }
+
+    pub fn load_trainer(&mut self, data: &[u8]) -> Result<(), Box<dyn std::error::Error + 'static>> {
+        if data.len() > TRAINER_SIZE {
+            return Err(format!("trainer is {} bytes, the trainer region holds {}", data.len(), TRAINER_SIZE).into());
+        }
+        let start = TRAINER_ADDR as usize;
+        self.memory[start..start + data.len()].copy_from_slice(data);
+        self.trainer = true;
+        Ok(())
+    }
+
+    // call the trainer until its final RET. it may patch memory, but the
+    // ROM's registers, I and pc are restored afterwards
+    fn run_trainer(&mut self) {
+        let (v, i, pc, sp, opcode) = (self.v, self.i, self.pc, self.sp, self.opcode);
+        // no timer ticks, and so no nested trainer calls, while it runs
+        let cycle_timers = self.cycle_timers;
+        self.cycle_timers = false;
+
+        self.pc = TRAINER_ADDR;
+        for _ in 0..TRAINER_BUDGET {
+            if self.sp == sp && self.opcode_at(self.pc) == 0x00EE {
+                break;
+            }
+            self.emulate_cycle();
+        }
+
+        self.v = v;
+        self.i = i;
+        self.pc = pc;
+        self.sp = sp;
+        self.opcode = opcode;
+        self.cycle_timers = cycle_timers;
+    }
+
     pub fn load_program(&mut self, path_arg: &str) -> Result<(), Box<dyn std::error::Error + 'static>> {
-        // load program into memory at memory[512] (0x200)
         let path = Path::new(path_arg);
         let data: Vec<u8> = fs::read(&path)?;
-        
-        for i in 0..data.len() {
-            self.memory[i + 512] = data[i];
-            // println!("memory[{}]: {}", (i + 512), data[i]);
+        Ok(self.load_program_bytes(&data)?)
+    }
+
+    // load a program already in memory, for ROMs bundled into the binary or
+    // loaded without a filesystem
+    pub fn load_program_bytes(&mut self, data: &[u8]) -> Result<(), Chip8Error> {
+        // load program into memory at memory[512] (0x200), leaving out
+        // skip_header bytes at the start of the file and skip_trailer at the
+        // end for dumps wrapped in extra metadata
+        let skipped = self.skip_header + self.skip_trailer;
+        if skipped > data.len() {
+            return Err(LoadError::SkipTooLong { skipped, size: data.len() }.into());
+        }
+        let body = &data[self.skip_header..data.len() - self.skip_trailer];
+        if body.len() > self.memory.len() - 512 {
+            return Err(LoadError::TooLarge { size: body.len(), max: self.memory.len() - 512 }.into());
         }
+        self.rom_hash = fnv1a(body.iter().copied());
+
+        self.memory[512..512 + body.len()].copy_from_slice(body);
 
         Ok(())
     }
 
+    // lit and unlit pixel colours, RGBA, for draw and the image exports
+    pub fn set_colors(&mut self, fg: [u8; 4], bg: [u8; 4]) {
+        self.palette.foreground = fg;
+        self.palette.background = bg;
+    }
+
+    // the display size, 64x32, or 128x64 in hi-res mode
+    pub fn resolution(&self) -> (u32, u32) {
+        if self.hires {
+            (HIRES_WIDTH, HIRES_HEIGHT)
+        } else {
+            (WIDTH, HEIGHT)
+        }
+    }
+
+    // `frame` is RGBA at the current resolution
     pub fn draw(&self, frame: &mut [u8]) {
-        for (i, pixel) in frame.chunks_exact_mut(4).enumerate() {
-            let x = i % WIDTH as usize;
-            let y = i / WIDTH as usize;
+        // walk the frame and the display a row at a time, side by side
+        let (width, height) = self.resolution();
+        for (row, line) in frame.chunks_exact_mut(width as usize * 4).zip(self.gfx.iter().take(height as usize)) {
+            for (pixel, &planes) in row.chunks_exact_mut(4).zip(line.iter()) {
+                pixel.copy_from_slice(&self.palette.color(planes));
+            }
+        }
+    }
+
+    // the pixel at column x, row y: 0 when unlit, otherwise its plane bits,
+    // which is 1 unless an XO-CHIP ROM draws on the second plane
+    pub fn pixel(&self, x: usize, y: usize) -> u8 {
+        self.gfx[y][x]
+    }
+
+    pub fn set_pixel(&mut self, x: usize, y: usize, value: u8) {
+        self.gfx[y][x] = value;
+    }
+
+    pub fn draw_memory(&self, frame: &mut [u8]) {
+        // one pixel per byte of memory, brighter for larger values
+        for (pixel, byte) in frame.chunks_exact_mut(4).zip(self.memory.iter()) {
+            pixel.copy_from_slice(&[*byte, *byte, *byte, 0xff]);
+        }
+    }
+
+    pub fn gfx_hash(&self) -> u64 {
+        // hash of the display, row by row
+        let (width, height) = self.resolution();
+        let pixels = self.gfx.iter()
+            .take(height as usize)
+            .flat_map(|row| row[..width as usize].iter().copied());
+        fnv1a(pixels)
+    }
+
+    // mean instructions executed per display update, counting the update
+    // itself, or None before the first one
+    pub fn instructions_per_draw(&self) -> Option<f64> {
+        let (draws, instructions) = self.draw_intervals;
+        if draws == 0 {
+            None
+        } else {
+            Some(instructions as f64 / draws as f64)
+        }
+    }
+
+    fn count_draw(&mut self) {
+        self.draw_intervals.0 += 1;
+        self.draw_intervals.1 += self.instructions_since_draw;
+        self.instructions_since_draw = 0;
+    }
+
+    // high-water mark of the stack, out of 16 levels
+    pub fn max_stack_depth(&self) -> usize {
+        self.max_sp
+    }
+
+    // registers and timers for inspection. rewind_state() is the full state
+    // for rewinding with restore()
+    pub fn snapshot(&self) -> MachineSnapshot {
+        MachineSnapshot {
+            pc:          self.pc,
+            i:           self.i,
+            sp:          self.sp,
+            v:           self.v,
+            delay_timer: self.delay_timer,
+            sound_timer: self.sound_timer,
+            opcode:      self.opcode,
+        }
+    }
+
+    pub fn rewind_state(&self) -> Snapshot {
+        Snapshot {
+            opcode:        self.opcode,
+            memory:        self.memory,
+            v:             self.v,
+            i:             self.i,
+            pc:            self.pc,
+            gfx:           self.gfx,
+            hires:         self.hires,
+            selected_planes: self.selected_planes,
+            delay_timer:   self.delay_timer,
+            sound_timer:   self.sound_timer,
+            audio_buffer:  self.audio_buffer,
+            pitch:         self.pitch,
+            pattern_audio: self.pattern_audio,
+            stack:         self.stack,
+            sp:            self.sp,
+            key:           self.key,
+            draw_flag:     self.draw_flag,
+            halted:        self.halted,
+            exited:        self.exited,
+            error:         self.error,
+            timer_elapsed: self.timer_elapsed,
+            rng:           self.rng.clone(),
+            rpl:           self.rpl,
+        }
+    }
+
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self.opcode = snapshot.opcode;
+        self.memory = snapshot.memory;
+        self.v = snapshot.v;
+        self.i = snapshot.i;
+        self.pc = snapshot.pc;
+        self.gfx = snapshot.gfx;
+        self.hires = snapshot.hires;
+        self.selected_planes = snapshot.selected_planes;
+        self.delay_timer = snapshot.delay_timer;
+        self.sound_timer = snapshot.sound_timer;
+        self.audio_buffer = snapshot.audio_buffer;
+        self.pitch = snapshot.pitch;
+        self.pattern_audio = snapshot.pattern_audio;
+        self.stack = snapshot.stack;
+        self.sp = snapshot.sp;
+        self.key = snapshot.key;
+        self.draw_flag = snapshot.draw_flag;
+        self.halted = snapshot.halted;
+        self.exited = snapshot.exited;
+        self.error = snapshot.error;
+        self.timer_elapsed = snapshot.timer_elapsed;
+        self.rng = snapshot.rng.clone();
+        self.rpl = snapshot.rpl;
+    }
+
+    // the snapshot in a compact binary form, for saving to disk
+    #[cfg(feature = "serde")]
+    pub fn save_state(&self) -> Vec<u8> {
+        savestate::encode(&self.rewind_state())
+    }
+
+    // resume from bytes written by save_state. the machine is left alone if
+    // they can't be decoded
+    #[cfg(feature = "serde")]
+    pub fn load_state(&mut self, data: &[u8]) -> Result<(), Chip8Error> {
+        let snapshot = savestate::decode(data)?;
+        self.restore(&snapshot);
+        Ok(())
+    }
+
+    pub fn canonical_dump(&self) -> String {
+        // minimal machine state in a fixed format for diffing against other
+        // runs and emulators; changing this format breaks those comparisons
+        let v: Vec<String> = self.v.iter().map(|reg| format!("{:02X}", reg)).collect();
+        format!(
+            "PC {:04X}\nI {:04X}\nSP {:02X}\nV {}\nGFX {:016X}\n",
+            self.pc, self.i, self.sp, v.join(" "), self.gfx_hash()
+        )
+    }
 
-            let rgba = if self.gfx[x][y] != 0 {
-                [0xff, 0xff, 0xff, 0xff]
-            } else {
-                [0x00, 0x00, 0x00, 0xff]
-            };
+    // a hex dump of `range`, 16 bytes a line after the address of the first,
+    // cut short at the end of memory
+    pub fn dump_memory(&self, range: Range<usize>) -> String {
+        let end = range.end.min(self.memory.len());
+        let start = range.start.min(end);
+        self.memory[start..end].chunks(16).enumerate().map(|(line, bytes)| {
+            let hex: Vec<String> = bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            format!("{:03X}  {}\n", start + line * 16, hex.join(" "))
+        }).collect()
+    }
 
-            pixel.copy_from_slice(&rgba);
+    pub fn to_svg(&self, scale: u32) -> String {
+        // the display as vector graphics, one rect per lit pixel over a
+        // background fill, each pixel `scale` units square
+        let (width, height) = self.resolution();
+        let mut svg = format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" style=\"background-color:{}\">\n",
+            width * scale, height * scale, css_color(self.palette.background)
+        );
+        for y in 0..height {
+            for x in 0..width {
+                let planes = self.pixel(x as usize, y as usize);
+                if planes != 0 {
+                    svg.push_str(&format!(
+                        "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                        x * scale, y * scale, scale, scale, css_color(self.palette.color(planes))
+                    ));
+                }
+            }
         }
+        svg.push_str("</svg>\n");
+        svg
     }
 
-    fn log(&self, call: &str) {
-        println!("{:#0x}      {:04x}      {}", self.pc, self.opcode, call);
+    // compare the display against a reference PNG, for image-based tests.
+    // the image may be any whole multiple of the native resolution; each
+    // cell is sampled at its centre and counts as lit when brighter than
+    // half intensity. differing pixels are returned as (x, y) in row order.
+    // panics if the image can't be read or has the wrong size, like the
+    // assert macros it is meant to sit alongside
+    pub fn assert_matches_image(&self, path: &str) -> Result<(), Vec<(usize, usize)>> {
+        let file = fs::File::open(path).unwrap_or_else(|err| panic!("{}: {}", path, err));
+        let mut decoder = png::Decoder::new(file);
+        decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+        let mut reader = decoder.read_info().unwrap_or_else(|err| panic!("{}: {}", path, err));
+        let mut buf = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut buf).unwrap_or_else(|err| panic!("{}: {}", path, err));
+
+        let (width, height) = (info.width as usize, info.height as usize);
+        let (cols, rows) = self.resolution();
+        let (cols, rows) = (cols as usize, rows as usize);
+        assert!(
+            width > 0 && height > 0 && width % cols == 0 && height % rows == 0,
+            "{}: {}x{} is not a multiple of {}x{}", path, width, height, cols, rows
+        );
+        let (scale_x, scale_y) = (width / cols, height / rows);
+        let samples = info.color_type.samples();
+
+        let mut diffs = Vec::new();
+        for y in 0..rows {
+            for x in 0..cols {
+                let offset = (y * scale_y + scale_y / 2) * info.line_size + (x * scale_x + scale_x / 2) * samples;
+                // grey, or the mean of red, green and blue; alpha is ignored
+                let channels = if samples >= 3 { 3 } else { 1 };
+                let brightness = buf[offset..offset + channels].iter().map(|&c| c as u32).sum::<u32>() / channels as u32;
+                if (brightness >= 0x80) != (self.pixel(x, y) != 0) {
+                    diffs.push((x, y));
+                }
+            }
+        }
+
+        if diffs.is_empty() {
+            Ok(())
+        } else {
+            Err(diffs)
+        }
+    }
+
+    #[cfg(test)]
+    pub fn gfx_to_string(&self) -> String {
+        // render the display as text, one line per row, '#' for lit pixels
+        self.to_text('#', '.')
+    }
+
+    pub fn to_text(&self, on: char, off: char) -> String {
+        self.to_text_with(on, off, "", "\n")
+    }
+
+    pub fn to_text_with(&self, on: char, off: char, col_sep: &str, row_sep: &str) -> String {
+        // render the display as text, `on` for lit pixels and `off` for the
+        // rest; `col_sep` goes between pixels and `row_sep` after each row
+        let (width, height) = self.resolution();
+        let mut out = String::new();
+        for y in 0..height as usize {
+            for x in 0..width as usize {
+                if x > 0 {
+                    out.push_str(col_sep);
+                }
+                out.push(if self.pixel(x, y) != 0 { on } else { off });
+            }
+            out.push_str(row_sep);
+        }
+        out
+    }
+
+    // run `handler` instead of the built-in instruction for any opcode where
+    // `opcode & mask == value`, including opcodes that are otherwise unknown.
+    // overrides take precedence over built-ins, and the most recently
+    // registered matching override wins
+    pub fn override_opcode(&mut self, mask: u16, value: u16, handler: OpcodeHandler) {
+        self.overrides.push((mask, value, handler));
+    }
+
+    fn override_for(&self, opcode: u16) -> Option<OpcodeHandler> {
+        self.overrides.iter().rev()
+            .find(|(mask, value, _)| opcode & mask == *value)
+            .map(|(_, _, handler)| *handler)
+    }
+
+    // receive the machine's events from now on, replacing any earlier
+    // subscriber
+    pub fn subscribe(&mut self) -> Receiver<Event> {
+        let (tx, rx) = mpsc::channel();
+        self.events = Some(tx);
+        rx
+    }
+
+    fn emit(&self, event: Event) {
+        if let Some(tx) = &self.events {
+            // a subscriber that hung up just stops getting events
+            let _ = tx.send(event);
+        }
+    }
+
+    // report a likely ROM bug without stopping it
+    fn warn(&mut self, msg: String) {
+        log::warn!("{}", msg);
+        self.last_warning = Some(msg);
+    }
+
+    // memory write from the ROM, checked against the last sprite drawn when
+    // --warn-sprite-write is on
+    fn store(&mut self, addr: usize, value: u8) {
+        if let Some((start, len)) = self.sprite_source {
+            if self.warn_sprite_write && (start as usize..start as usize + len as usize).contains(&addr) {
+                self.warn(format!("{:#05x} wrote {:#05x}, part of the sprite drawn from {:#05x}", self.pc, addr, start));
+            }
+        }
+        self.memory[addr % self.memory.len()] = value;
+    }
+
+    // memory read, wrapping around the 4K address space like stores
+    fn read(&self, addr: usize) -> u8 {
+        self.memory[addr % self.memory.len()]
+    }
+
+    // keep the last trace_len instructions. the history grows to twice that
+    // before the oldest half is dropped, so it stays one contiguous slice
+    fn record(&mut self, decoded: &DecodedInstruction) {
+        if self.trace_len == 0 {
+            return;
+        }
+        if self.history.len() >= 2 * self.trace_len {
+            self.history.drain(..self.history.len() - self.trace_len + 1);
+        }
+        self.history.push(TraceEntry { pc: self.pc, opcode: self.opcode, mnemonic: decoded.mnemonic });
+    }
+
+    // the instructions most recently run, oldest first
+    pub fn trace(&self) -> &[TraceEntry] {
+        &self.history[self.history.len().saturating_sub(self.trace_len)..]
+    }
+
+    fn log(&mut self, call: &str) {
+        // sample the trace so a fast ROM is not slowed down by its own output
+        self.trace_count += 1;
+        if self.trace_count < self.trace_every {
+            return;
+        }
+        self.trace_count = 0;
+        self.trace_lines += 1;
+        log::trace!("{:#0x}      {:04x}      {}", self.pc, self.opcode, call);
     }
 
     fn get_opcode(&mut self) -> u16 {
         // fetch opcode
-        (self.memory[self.pc as usize] as u16) << 8 | (self.memory[self.pc as usize + 1] as u16)
+        self.opcode_at(self.pc)
+    }
+
+    fn opcode_at(&self, addr: u16) -> u16 {
+        (self.read(addr as usize) as u16) << 8 | (self.read(addr as usize + 1) as u16)
+    }
+
+    fn skip_next(&mut self) {
+        // skip the instruction after this one; the XO-CHIP long load F000 NNNN
+        // is four bytes long, so skipping it needs an extra two bytes
+        if self.opcode_at(self.pc + 2) == 0xF000 {
+            self.pc += 6;
+        } else {
+            self.pc += 4;
+        }
     }
 
+    pub fn is_idle(&self) -> bool {
+        // nothing can change until input arrives when the ROM has halted, is
+        // blocked on Fx0A, or is polling a key with a skip followed by a jump
+        // straight back to it
+        if self.halted || self.paused {
+            return true;
+        }
+
+        let current = DecodedInstruction::new(self.opcode_at(self.pc));
+        if current.nibbles.0 == 0x0f && current.kk == 0x0a {
+            return self.key == [false; 16];
+        }
+
+        // the loop may be caught on either of its two instructions
+        let start = if current.nibbles.0 == 0x01 && current.nnn == self.pc.wrapping_sub(2) {
+            current.nnn
+        } else {
+            self.pc
+        };
+        if start as usize + 3 >= self.memory.len() || self.opcode_at(start + 2) != 0x1000 | start {
+            return false;
+        }
+
+        let check = DecodedInstruction::new(self.opcode_at(start));
+        let pressed = self.key[(self.v[check.x] & 0x0F) as usize];
+        match (check.nibbles.0, check.kk) {
+            (0x0e, 0x9e) => !pressed,       // SKP Vx; JP back: waiting for a press
+            (0x0e, 0xa1) => pressed,        // SKNP Vx; JP back: waiting for a release
+            _ => false,
+        }
+    }
+
+    pub fn decode(&self) -> DecodedInstruction {
+        // decode the instruction at pc
+        DecodedInstruction::new(self.opcode_at(self.pc))
+    }
+
+    // run one instruction, logging unknown opcodes. faults are left in
+    // `error` for the frontend, and wrapped memory accesses are normal for
+    // some ROMs
     pub fn emulate_cycle(&mut self) {
+        if let Err(Chip8Error::UnknownOpcode(opcode)) = self.step() {
+            log::debug!("Unknown opcode: {:#0X}", opcode);
+        }
+    }
+
+    // stop running instructions until resume(), for stepping through a ROM
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.watch_hit = None;
+    }
+
+    // run exactly one instruction, paused or not, and stay paused after it
+    // if the machine was paused before
+    pub fn step_once(&mut self) -> Result<(), Chip8Error> {
+        let paused = self.paused;
+        self.paused = false;
+        self.breakpoint_hit = Some(self.pc);
+        let result = self.step();
+        self.paused |= paused;
+        result
+    }
+
+    // rows and bytes per row of a Dxyn sprite: n rows of one byte, or for
+    // Dxy0 in hi-res mode a 16x16 sprite of two bytes a row
+    fn sprite_shape(&self, n: usize) -> (usize, usize) {
+        if n == 0 && self.hires {
+            (16, 2)
+        } else {
+            (n, 1)
+        }
+    }
+
+    // the first address past the end of memory the instruction will touch
+    fn overrun(&self, decoded: &DecodedInstruction) -> Option<u16> {
+        let len = match decoded.class {
+            "Dxyn" => {
+                let (rows, row_bytes) = self.sprite_shape(decoded.n);
+                rows * row_bytes * self.selected_planes.count_ones() as usize
+            }
+            "F002" => 16,
+            "Fx33" => 3,
+            "Fx55" | "Fx65" => decoded.x + 1,
+            _ => 0,
+        };
+        let size = if decoded.class == "F000" { 4 } else { 2 };
+        if self.pc as usize + size > self.memory.len() {
+            Some(self.memory.len() as u16)
+        } else if len > 0 && self.i as usize + len > self.memory.len() {
+            Some(self.i.max(self.memory.len() as u16))
+        } else {
+            None
+        }
+    }
+
+    // run one instruction and report what went wrong, if anything: a fault
+    // that stopped the interpreter (now or earlier), an unknown opcode, or a
+    // memory access that wrapped past 0xFFF. nothing runs after the
+    // interpreter has exited or while a watchpoint has it paused. reaching a
+    // breakpoint pauses before the instruction there runs; it runs on resume
+    pub fn step(&mut self) -> Result<(), Chip8Error> {
+        if let Some(err) = self.error {
+            return Err(err);
+        }
+        if self.exited || self.paused {
+            return Ok(());
+        }
+        if self.breakpoints.contains(&self.pc) && self.breakpoint_hit != Some(self.pc) {
+            self.breakpoint_hit = Some(self.pc);
+            self.paused = true;
+            return Ok(());
+        }
+        self.breakpoint_hit = None;
+        self.watch_hit = None;
+
+        // instructions are two bytes and always aligned; an odd pc means a
+        // bad jump and everything fetched from here on is garbage
+        if self.pc % 2 == 1 {
+            if self.strict_align {
+                self.error = Some(Chip8Error::MisalignedPc(self.pc));
+                return Err(Chip8Error::MisalignedPc(self.pc));
+            }
+            if self.warn_misalign {
+                self.warn(format!("instruction fetch from odd address {:#05x}", self.pc));
+            }
+        }
 
+        // jumps can reach past the top of memory, where addresses wrap
+        self.pc &= 0xFFF;
         self.opcode = self.get_opcode();
-        
-        let nibbles = (
-            (self.opcode & 0xF000) >> 12 as u8,
-            (self.opcode & 0x0F00) >> 8 as u8,
-            (self.opcode & 0x00F0) >> 4 as u8,
-            (self.opcode & 0x000F) as u8,
-        );
+        self.instructions += 1;
+        self.instructions_since_draw += 1;
 
-        let x        = ((self.opcode & 0x0F00) >> 8) as usize;
-        let y        = ((self.opcode & 0x00F0) >> 4) as usize;
-        let n        = (self.opcode & 0x000F) as usize;
-        let kk       = (self.opcode & 0x00FF) as u8;
-        let nnn      = self.opcode & 0x0FFF;
+        let decoded = self.decode();
+        let overrun = self.overrun(&decoded);
+        self.record(&decoded);
+        let DecodedInstruction { nibbles, x, y, n, kk, nnn, class, mnemonic, .. } = decoded;
+        let mut unknown = false;
+        if let Some(profile) = &mut self.profile {
+            *profile.entry((class, mnemonic)).or_insert(0) += 1;
+        }
 
         match nibbles {
+            _ if self.override_for(self.opcode).is_some() => self.op_override(),
+            (0x00, 0x00, 0x0c, _)    => self.op_00cn(n),
             (0x00, 0x00, 0x0e, 0x00) => self.op_00e0(),
             (0x00, 0x00, 0x0e, 0x0e) => self.op_00ee(),
+            (0x00, 0x00, 0x0f, 0x0b) => self.op_00fb(),
+            (0x00, 0x00, 0x0f, 0x0c) => self.op_00fc(),
+            (0x00, 0x00, 0x0f, 0x0d) => self.op_00fd(),
+            (0x00, 0x00, 0x0f, 0x0e) => self.op_00fe(),
+            (0x00, 0x00, 0x0f, 0x0f) => self.op_00ff(),
             (0x01, _, _, _)          => self.op_1nnn(nnn),
             (0x02, _, _, _)          => self.op_2nnn(nnn),
             (0x03, _, _, _)          => self.op_3xkk(x, kk),
@@ -140,9 +1149,9 @@ impl Chip8 {
             (0x08, _, _, 0x03)       => self.op_8xy3(x, y),
             (0x08, _, _, 0x04)       => self.op_8xy4(x, y),
             (0x08, _, _, 0x05)       => self.op_8xy5(x, y),
-            (0x08, _, _, 0x06)       => self.op_8x06(x),
+            (0x08, _, _, 0x06)       => self.op_8x06(x, y),
             (0x08, _, _, 0x07)       => self.op_8xy7(x, y),
-            (0x08, _, _, 0x0e)       => self.op_8x0e(x),
+            (0x08, _, _, 0x0e)       => self.op_8x0e(x, y),
             (0x09, _, _, 0x00)       => self.op_9xy0(x, y),
             (0x0a, _, _, _)          => self.op_annn(nnn),
             (0x0b, _, _, _)          => self.op_bnnn(nnn),
@@ -150,47 +1159,313 @@ impl Chip8 {
             (0x0d, _, _, _)          => self.op_dxyn(x, y, n),
             (0x0e, _, 0x09, 0x0e)    => self.op_ex9e(x),
             (0x0e, _, 0x0a, 0x01)    => self.op_exa1(x),
+            (0x0f, 0x00, 0x00, 0x00) => self.op_f000(),
+            (0x0f, _, 0x00, 0x01)    => self.op_fn01(x),
+            (0x0f, 0x00, 0x00, 0x02) => self.op_f002(),
             (0x0f, _, 0x00, 0x07)    => self.op_fx07(x),
             (0x0f, _, 0x00, 0x0a)    => self.op_fx0a(x),
             (0x0f, _, 0x01, 0x05)    => self.op_fx15(x),
             (0x0f, _, 0x01, 0x08)    => self.op_fx18(x),
             (0x0f, _, 0x01, 0x0e)    => self.op_fx1e(x),
             (0x0f, _, 0x02, 0x09)    => self.op_fx29(x),
+            (0x0f, _, 0x03, 0x00)    => self.op_fx30(x),
             (0x0f, _, 0x03, 0x03)    => self.op_fx33(x),
+            (0x0f, _, 0x03, 0x0a)    => self.op_fx3a(x),
             (0x0f, _, 0x05, 0x05)    => self.op_fx55(x),
             (0x0f, _, 0x06, 0x05)    => self.op_fx65(x),
-            _ => println!("Unknown opcode: {:#0X}", self.opcode),
+            (0x0f, _, 0x07, 0x05)    => self.op_fx75(x),
+            (0x0f, _, 0x08, 0x05)    => self.op_fx85(x),
+            _ => {
+                unknown = true;
+                self.emit(Event::UnknownOpcode(self.opcode));
+            }
         }
 
-    
+        // deterministic timing: one timer tick per 60Hz worth of instructions
+        if self.cycle_timers {
+            self.timer_elapsed += self.cycle_time();
+            if self.timer_elapsed >= TIMER_PERIOD {
+                self.timer_elapsed -= TIMER_PERIOD;
+                self.tick_timers();
+            }
+        }
+
+        self.check_watchpoints();
+
+        match (self.error, overrun) {
+            (Some(err), _) => Err(err),
+            _ if unknown => Err(Chip8Error::UnknownOpcode(self.opcode)),
+            (None, Some(addr)) => Err(Chip8Error::MemoryOutOfBounds(addr)),
+            (None, None) => Ok(()),
+        }
+    }
+
+    // pause when a watchpoint's condition becomes true; it fires again only
+    // after the condition has been false in between
+    pub fn add_watchpoint(&mut self, watch: Watch) {
+        let held = watch.holds(&self.v, &self.memory);
+        self.watchpoints.push((watch, held));
+    }
+
+    pub fn add_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.insert(addr);
+    }
+
+    pub fn remove_breakpoint(&mut self, addr: u16) {
+        self.breakpoints.remove(&addr);
+    }
+
+    // an address for debug output, with its source line when there is a map
+    pub fn location(&self, addr: u16) -> String {
+        match self.source_map.as_ref().and_then(|map| map.line(addr)) {
+            Some(line) => format!("{:#05x} ({})", addr, line),
+            None => format!("{:#05x}", addr),
+        }
+    }
+
+    fn check_watchpoints(&mut self) {
+        let mut hit = None;
+        for (watch, held) in &mut self.watchpoints {
+            let holds = watch.holds(&self.v, &self.memory);
+            if holds && !*held {
+                hit = Some(*watch);
+            }
+            *held = holds;
+        }
+        if hit.is_some() {
+            self.paused = true;
+            self.watch_hit = hit;
+        }
+    }
+
+    // instructions run per 60Hz frame at the clock speed, at least one
+    pub fn cycles_per_frame(&self) -> usize {
+        (self.clock_speed / 60).max(1) as usize
+    }
+
+    // the clock speed that runs `ipf` instructions per 60Hz frame
+    pub fn set_cycles_per_frame(&mut self, ipf: u32) {
+        self.clock_speed = ipf as u64 * 60;
+    }
+
+    pub fn cycle_time(&self) -> Duration {
+        // emulated time taken by one instruction
+        Duration::from_nanos(1_000_000_000 / self.clock_speed.max(1))
+    }
+
+    pub fn tick_timers(&mut self) {
+        // count both timers down towards zero
+        self.delay_timer = self.delay_timer.saturating_sub(1);
+        if self.sound_timer == 1 {
+            self.emit(Event::SoundStop);
+        }
+        self.sound_timer = self.sound_timer.saturating_sub(1);
+
+        // a ROM that never draws is usually stuck or not a game at all
+        self.frames_since_draw = self.frames_since_draw.saturating_add(1);
+        // warn once, as the count reaches the timeout
+        if self.no_draw_warning() && self.frames_since_draw == self.draw_timeout {
+            self.warn(format!("nothing drawn in {} frames, the ROM may be stuck or not graphical", self.draw_timeout));
+        }
+
+        if self.trainer {
+            self.run_trainer();
+        }
+    }
+
+    pub fn no_draw_warning(&self) -> bool {
+        self.draw_timeout > 0 && self.frames_since_draw >= self.draw_timeout
+    }
+
+    // run `cycles` instructions with no rendering or pacing, for tests and
+    // benchmarks, and return where pc ended up. a machine that has stopped
+    // runs nothing more
+    pub fn run_cycles(&mut self, cycles: usize) -> u16 {
+        for _ in 0..cycles {
+            self.emulate_cycle();
+        }
+        self.pc
+    }
+
+    pub fn run_for(&mut self, budget: Duration) -> usize {
+        // run instructions until about `budget` of emulated time has passed,
+        // returning how many were executed
+        let mut elapsed = Duration::ZERO;
+        let mut count = 0;
+        let cycle_time = self.cycle_time();
+        while !self.exited && elapsed + cycle_time <= budget {
+            self.emulate_cycle();
+            elapsed += cycle_time;
+            count += 1;
+        }
+        count
+    }
+
+    fn op_override(&mut self) {
+        if let Some(handler) = self.override_for(self.opcode) {
+            handler(self, self.opcode);
+        }
     }
 
+    // blank every plane of the display without running an instruction, for
+    // frontends and tests. 00E0 clears only the planes FN01 selected, and
+    // also counts as the ROM drawing
+    pub fn clear_display(&mut self) {
+        self.gfx = [[0x00; 128]; 64];
+        self.draw_flag = true;
+    }
+
+    // switching resolution clears the display, as Octo and XO-CHIP do, so
+    // nothing drawn at one resolution shows up misplaced at the other; with
+    // the SUPER-CHIP quirk the picture is rescaled to the new resolution
+    fn set_hires(&mut self, hires: bool) {
+        if self.quirks.hires_switch_clears {
+            self.clear_display();
+        } else if hires != self.hires {
+            self.rescale(hires);
+        }
+        self.hires = hires;
+    }
+
+    // redraw the display at the other resolution: each low-res pixel becomes
+    // a 2x2 block, and each 2x2 block becomes a low-res pixel lit in every
+    // plane any of its four pixels was lit in
+    fn rescale(&mut self, hires: bool) {
+        let before = self.gfx;
+        for (y, row) in self.gfx.iter_mut().enumerate() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = if hires {
+                    before[y / 2][x / 2]
+                } else if y < HEIGHT as usize && x < WIDTH as usize {
+                    before[2 * y][2 * x] | before[2 * y][2 * x + 1] | before[2 * y + 1][2 * x] | before[2 * y + 1][2 * x + 1]
+                } else {
+                    0
+                };
+            }
+        }
+        self.draw_flag = true;
+    }
+
+    // clear only the selected planes, for 00E0
+    fn clear_planes(&mut self) {
+        let keep = !self.selected_planes;
+        for pixel in self.gfx.iter_mut().flat_map(|row| row.iter_mut()) {
+            *pixel &= keep;
+        }
+        self.draw_flag = true;
+    }
+
+    // move the selected planes of the active display area `dx` pixels right
+    // and `dy` down, clearing the pixels scrolled in from outside
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let (width, height) = self.resolution();
+        let (width, height) = (width as isize, height as isize);
+        let planes = self.selected_planes;
+        let before = self.gfx;
+        for x in 0..width {
+            for y in 0..height {
+                let (from_x, from_y) = (x - dx, y - dy);
+                let inside = (0..width).contains(&from_x) && (0..height).contains(&from_y);
+                let moved = if inside { before[from_y as usize][from_x as usize] } else { 0 };
+                let pixel = &mut self.gfx[y as usize][x as usize];
+                *pixel = (*pixel & !planes) | (moved & planes);
+            }
+        }
+        self.draw_flag = true;
+        self.frames_since_draw = 0;
+        self.count_draw();
+        self.emit(Event::Draw);
+    }
+
+    pub fn op_00cn(&mut self, n: usize) {
+        // SCD nibble
+        // Scroll the display down n lines (SUPER-CHIP)
+        self.scroll(0, n as isize);
+        self.pc += 2;
+        self.log("SCD nibble");
+    }
     pub fn op_00e0(&mut self) {
         // CLS
-        // Clear the display.
-        self.gfx = [[0x00; 32]; 64];
-        self.draw_flag = true;
+        // Clear the display, or on XO-CHIP just the selected planes.
+        self.clear_planes();
+        self.frames_since_draw = 0;
+        self.count_draw();
+        self.emit(Event::Draw);
         self.pc += 2;
         self.log("CLS");
     }
     pub fn op_00ee(&mut self) {
         // RET
         // Return from a subroutine
+        //
+        // The stack already holds the address after the CALL (see op_2nnn).
+        if self.sp == 0 {
+            self.error = Some(Chip8Error::StackUnderflow);
+            return;
+        }
         self.sp -= 1;
         self.pc = self.stack[self.sp];
         self.log("RET");
     }
+    pub fn op_00fb(&mut self) {
+        // SCR
+        // Scroll the display right 4 pixels (SUPER-CHIP)
+        self.scroll(4, 0);
+        self.pc += 2;
+        self.log("SCR");
+    }
+    pub fn op_00fc(&mut self) {
+        // SCL
+        // Scroll the display left 4 pixels (SUPER-CHIP)
+        self.scroll(-4, 0);
+        self.pc += 2;
+        self.log("SCL");
+    }
+    pub fn op_00fd(&mut self) {
+        // EXIT
+        // Exit the interpreter (SUPER-CHIP)
+        self.exited = true;
+        self.emit(Event::Exited);
+        self.log("EXIT");
+    }
+    pub fn op_00fe(&mut self) {
+        // LOW
+        // Switch to the 64x32 display (SUPER-CHIP)
+        self.set_hires(false);
+        self.pc += 2;
+        self.log("LOW");
+    }
+    pub fn op_00ff(&mut self) {
+        // HIGH
+        // Switch to the 128x64 display (SUPER-CHIP)
+        self.set_hires(true);
+        self.pc += 2;
+        self.log("HIGH");
+    }
     pub fn op_1nnn(&mut self, nnn: u16) {
         // JP addr
         // Jump to location nnn
+        // A jump to its own address is the usual way for a ROM to stop.
+        self.halted = nnn == self.pc;
         self.pc = nnn;
         self.log("JP addr");
     }
     pub fn op_2nnn(&mut self, nnn: u16) {
         // CALL addr
         // Call subroutine at nnn
+        //
+        // The return address pushed is pc + 2, the instruction after the CALL,
+        // and RET restores it as is. Keep the two in step.
+        if self.sp == self.stack.len() {
+            self.error = Some(Chip8Error::StackOverflow);
+            return;
+        }
         self.stack[self.sp] = self.pc + 2;
         self.sp += 1;
+        self.max_sp = self.max_sp.max(self.sp);
+        if let Some(edges) = &mut self.call_edges {
+            edges.insert((self.pc, nnn));
+        }
         self.pc = nnn;
         self.log("CALL addr");
     }
@@ -198,7 +1473,7 @@ impl Chip8 {
         // SE Vx, byte
         // Skip next instruction if Vx == kk.
         if self.v[x] == kk {
-            self.pc += 4;
+            self.skip_next();
         } else {
             self.pc += 2;
         }
@@ -208,7 +1483,7 @@ impl Chip8 {
         // SNE Vx, byte
         // Skip next instruction if Vx != kk.
         if self.v[x] != kk {
-            self.pc += 4;
+            self.skip_next();
         } else {
             self.pc += 2;
         }
@@ -218,7 +1493,7 @@ impl Chip8 {
         // SE Vx, Vy
         // Skip next instruction if Vx = Vy
         if self.v[x] == self.v[y] {
-            self.pc += 4;
+            self.skip_next();
         } else {
             self.pc += 2;
         }
@@ -249,6 +1524,7 @@ impl Chip8 {
         // OR Vx, Vy
         // Set Vx = Vx OR Vy
         self.v[x] = self.v[x] | self.v[y];
+        self.logic_vf_reset();
         self.pc += 2;
         self.log("OR Vx, Vy");
     }
@@ -256,6 +1532,7 @@ impl Chip8 {
         // AND Vx, Vy
         // Set Vx = Vx AND Vy
         self.v[x] &= self.v[y];
+        self.logic_vf_reset();
         self.pc += 2;
         self.log("AND Vx, Vy");
     }
@@ -263,9 +1540,16 @@ impl Chip8 {
         // XOR Vx, Vy
         // Set Vx = Vx XOR Vy
         self.v[x] ^= self.v[y];
+        self.logic_vf_reset();
         self.pc += 2;
         self.log("XOR Vx, Vy");
     }
+    // the COSMAC VIP's logic ops clobbered VF
+    fn logic_vf_reset(&mut self) {
+        if self.quirks.logic_resets_vf {
+            self.v[0xF] = 0;
+        }
+    }
     pub fn op_8xy4(&mut self, x: usize, y: usize) {
         // ADD Vx, Vy
         // Set Vx = Vx + Vy, set VF = carry
@@ -296,14 +1580,23 @@ impl Chip8 {
         self.pc += 2;
         self.log("SUB Vx, Vy");
     }
-    pub fn op_8x06(&mut self, x: usize) {
+    pub fn op_8x06(&mut self, x: usize, y: usize) {
         // SHR Vx {, Vy}
-        // Set Vx = Vx SHR 1
-        self.v[0xF] = self.v[x] & 1;
-        self.v[x] >>= 1;
+        // Set Vx = Vx SHR 1, or Vy SHR 1 with the shift quirk
+        let value = self.shift_source(x, y);
+        self.v[0xF] = value & 1;
+        self.v[x] = value >> 1;
         self.pc += 2;
         self.log("SHR Vx {, Vy}");
     }
+    // the COSMAC VIP shifted Vy into Vx; SUPER-CHIP shifts Vx in place
+    fn shift_source(&self, x: usize, y: usize) -> u8 {
+        if self.quirks.shift_uses_vy {
+            self.v[y]
+        } else {
+            self.v[x]
+        }
+    }
     pub fn op_8xy7(&mut self, x: usize, y: usize) {
         // SUBN Vx, Vy
         // Set Vx = Vy - Vx, set VF = NOT borrow
@@ -316,11 +1609,12 @@ impl Chip8 {
         self.pc += 2;
         self.log("SUBN Vx, Vy");
     }
-    pub fn op_8x0e(&mut self, x: usize) {
+    pub fn op_8x0e(&mut self, x: usize, y: usize) {
         // SHL Vx {, Vy}
-        // Set Vx = Vx SHL 1
-        self.v[0xF] = (self.v[x] & 0x80) >> 7;
-        self.v[x] <<= 1;
+        // Set Vx = Vx SHL 1, or Vy SHL 1 with the shift quirk
+        let value = self.shift_source(x, y);
+        self.v[0xF] = (value & 0x80) >> 7;
+        self.v[x] = value << 1;
         self.pc += 2;
         self.log("SHL Vx {, Vy}");
     }
@@ -328,7 +1622,7 @@ impl Chip8 {
         // SNE Vx, Vy
         // Skip next instruction if Vx != Vy
         if self.v[x] != self.v[y] {
-            self.pc += 4;
+            self.skip_next();
         } else {
             self.pc += 2;
         }
@@ -343,15 +1637,15 @@ impl Chip8 {
     }
     pub fn op_bnnn(&mut self, nnn: u16) {
         // JP V0, addr
-        // Jump to location nnn + V0
-        self.pc = nnn + (self.v[0] as u16);
+        // Jump to location nnn + V0, or xnn + Vx on SUPER-CHIP
+        let x = if self.quirks.jump_uses_vx { (nnn >> 8) as usize } else { 0 };
+        self.pc = nnn + (self.v[x] as u16);
         self.log("JP V0, addr");
     }
     pub fn op_cxkk(&mut self, x: usize, kk: u8) {
         // RND Vx, byte
         // Set Vx = random byte AND kk
-        let mut rng = rand::thread_rng();
-        self.v[x] = rng.gen::<u8>() & kk;
+        self.v[x] = self.rng.gen::<u8>() & kk;
         self.pc += 2;
         self.log("RND Vx, byte");
     }
@@ -363,28 +1657,69 @@ impl Chip8 {
         // existing screen. If this causes any pixels to be erased, VF is set to 1, otherwise it is set
         // to 0. If the sprite is positioned so part of it is outside the coordinates of the display,
         // it wraps around to the opposite side of the screen.
+        //
+        // The starting position always wraps. The wrap_x and wrap_y quirks decide whether the parts
+        // of the sprite that run off the right and bottom edges wrap or are clipped.
+        //
+        // In hi-res mode Dxy0 draws a 16x16 sprite from 32 bytes, two to a row (SUPER-CHIP).
+        //
+        // The sprite goes on each plane selected by FN01, the first plane's data first and the
+        // second's straight after it (XO-CHIP).
     
+        // read the coordinates before VF is cleared, so VF can be used as Vx or Vy
+        let vx = self.v[x] as usize;
+        let vy = self.v[y] as usize;
         self.v[0xF] = 0;
+        let (rows, row_bytes) = self.sprite_shape(n);
+        let selected = self.selected_planes.count_ones() as usize;
+        self.sprite_source = Some((self.i, (rows * row_bytes * selected) as u16));
 
-        for byte in 0..n {
-            let dxyn_y = (self.v[y] as usize + byte as usize) % 32;
-            for bit in 0..8 {
-                let dxyn_x = (self.v[x] as usize + bit as usize) % 64;
-                let color = (self.memory[(self.i as usize + byte) as usize] >> (7 - bit)) & 1;
-                self.v[0xf] |= color & self.gfx[dxyn_x][dxyn_y];
-                self.gfx[dxyn_x][dxyn_y] ^= color;
+        let (width, height) = self.resolution();
+        let (width, height) = (width as usize, height as usize);
+        let mut source = self.i as usize;
+        for plane in 0..PLANES {
+            let bit = 1 << plane;
+            if self.selected_planes & bit == 0 {
+                continue;
+            }
+            for row in 0..rows {
+                let dxyn_y = vy % height + row;
+                if dxyn_y >= height && !self.quirks.wrap_y {
+                    break;
+                }
+                let dxyn_y = dxyn_y % height;
+                for col in 0..row_bytes * 8 {
+                    let dxyn_x = vx % width + col;
+                    if dxyn_x >= width && !self.quirks.wrap_x {
+                        break;
+                    }
+                    let dxyn_x = dxyn_x % width;
+                    let byte = self.read(source + row * row_bytes + col / 8);
+                    let color = (byte >> (7 - col % 8)) & 1;
+                    let pixel = self.pixel(dxyn_x, dxyn_y);
+                    let lit = (pixel & bit != 0) as u8;
+                    self.v[0xf] |= color & lit;
+                    self.set_pixel(dxyn_x, dxyn_y, pixel ^ (color * bit));
+                }
             }
+            source += rows * row_bytes;
         }
 
         self.draw_flag = true;
+        self.frames_since_draw = 0;
+        self.count_draw();
+        self.emit(Event::Draw);
+        if self.v[0xf] == 1 {
+            self.emit(Event::Collision { x: (vx % width) as u8, y: (vy % height) as u8 });
+        }
         self.pc += 2;
         self.log("DRW Vx, Vy, nibble");
     }
     pub fn op_ex9e(&mut self, x: usize) {
         // SKP Vx
         // Skip next instruction if key with the value of Vx is pressed
-        if self.key[self.v[x] as usize] == 1 {
-            self.pc += 4;
+        if self.key[self.v[x] as usize & 0xF] {
+            self.skip_next();
         } else {
             self.pc += 2;
         }
@@ -393,13 +1728,39 @@ impl Chip8 {
     pub fn op_exa1(&mut self, x: usize) {
         // SKNP Vx
         // Skip next instruction if key with the value of Vx is not pressed
-        if self.key[self.v[x] as usize] != 1 {
-            self.pc += 4;
+        if !self.key[self.v[x] as usize & 0xF] {
+            self.skip_next();
         } else {
             self.pc += 2;
         }
         self.log("SKNP Vx");
     }
+    pub fn op_f000(&mut self) {
+        // LD I, long addr
+        // Set I = the 16-bit address in the next two bytes (XO-CHIP)
+        self.i = self.opcode_at(self.pc + 2);
+        self.pc += 4;
+        self.log("LD I, long addr");
+    }
+    pub fn op_fn01(&mut self, n: usize) {
+        // PLANE n
+        // Select the planes Dxyn, 00E0 and the scrolls work on, bit 0 the first (XO-CHIP)
+        self.selected_planes = n as u8 & 0b11;
+        self.pc += 2;
+        self.log("PLANE n");
+    }
+    pub fn op_f002(&mut self) {
+        // AUDIO
+        // Load the 16-byte sound pattern at I, played in place of the buzzer (XO-CHIP)
+        let mut pattern = [0; 16];
+        for (n, byte) in pattern.iter_mut().enumerate() {
+            *byte = self.read(self.i as usize + n);
+        }
+        self.audio_buffer = pattern;
+        self.pattern_audio = true;
+        self.pc += 2;
+        self.log("AUDIO");
+    }
     pub fn op_fx07(&mut self, x: usize) {
         // LD Vx, DT
         // Set Vx = delay timer value
@@ -410,13 +1771,11 @@ impl Chip8 {
     pub fn op_fx0a(&mut self, x: usize) {
         // LD Vx, K
         // Wait for a key press, store the value of the key in Vx
-        if self.key != [0; 16] {
-            for i in 0..15 {
-                if self.key[i] != 0 {
-                    self.v[x] = i as u8;
-                }
-            }
-
+        //
+        // With no key down pc stays put, so the instruction runs again next
+        // cycle. With several down, the lowest key wins.
+        if let Some(key) = (0..16).find(|&key| self.key[key]) {
+            self.v[x] = key as u8;
             self.pc += 2;
             self.log("LD Vx, K");
         }
@@ -431,39 +1790,75 @@ impl Chip8 {
     pub fn op_fx18(&mut self, x: usize) {
         // LD ST, Vx
         // Set sound timer = Vx
+        match (self.sound_timer, self.v[x]) {
+            (0, 0) => {}
+            (0, _) => self.emit(Event::SoundStart),
+            (_, 0) => self.emit(Event::SoundStop),
+            _      => {}
+        }
         self.sound_timer = self.v[x];
         self.pc += 2;
         self.log("LD ST, Vx");
     }
     pub fn op_fx1e(&mut self, x: usize) {
         // ADD I, Vx
-        // Set I = I + Vx
-        self.i += self.v[x] as u16;
+        // Set I = I + Vx; with the quirk, set VF = 1 if I passes 0xFFF, otherwise 0
+        let sum = self.i.wrapping_add(self.v[x] as u16);
+        if self.quirks.fx1e_sets_vf {
+            self.v[0xF] = (sum > 0xFFF) as u8;
+        }
+        self.i = sum;
         self.pc += 2;
         self.log("ADD I, Vx");
     }
     pub fn op_fx29(&mut self, x: usize) {
         // LD F, Vx
         // Set I = location of sprite for digit Vx
-        self.i = (self.v[x] as u16) * 5;
+        self.i = nibble_to_font_addr(self.v[x]);
         self.pc += 2;
         self.log("LD F, Vx");
     }
+    pub fn op_fx30(&mut self, x: usize) {
+        // LD HF, Vx
+        // Set I = location of large sprite for digit Vx (SUPER-CHIP)
+        //
+        // Without the large font, or for a digit it has no glyph for, this
+        // falls back to the small glyph rather than pointing I at whatever
+        // happens to be in memory.
+        let digit = self.v[x] & 0x0F;
+        if self.large_font && digit <= 9 {
+            self.i = BIG_FONT_ADDR + digit as u16 * 10;
+        } else {
+            self.warn(format!("{:#05x} Fx30 with no large glyph for {:X}, using the small font", self.pc, digit));
+            self.i = nibble_to_font_addr(digit);
+        }
+        self.pc += 2;
+        self.log("LD HF, Vx");
+    }
     pub fn op_fx33(&mut self, x: usize) {
         // LD B, Vx
         // Store BCD representation of Vx in memory locations I, I+1, and I+2
-        self.memory[self.i as usize]       =   self.v[x] / 100;
-        self.memory[(self.i + 1) as usize] =  (self.v[x] % 100) / 10;
-        self.memory[(self.i + 2) as usize] =   self.v[x] % 10;
+        let bcd = byte_to_bcd(self.v[x]);
+        for (offset, digit) in bcd.iter().enumerate() {
+            self.store(self.i as usize + offset, *digit);
+        }
         self.pc += 2;
         self.log("LD B, Vx");
     }
+    pub fn op_fx3a(&mut self, x: usize) {
+        // PITCH Vx
+        // Set the sound pattern's playback pitch = Vx (XO-CHIP)
+        self.pitch = self.v[x];
+        self.pc += 2;
+        self.log("PITCH Vx");
+    }
     pub fn op_fx55(&mut self, x: usize) {
         // LD [I], Vx
         // Store registers V0 through Vx in memory starting at location I
         for i in 0..(x as u16) + 1 {
-            self.memory[(self.i + i) as usize] = self.v[i as usize];
+            self.store(self.i as usize + i as usize, self.v[i as usize]);
         }
+        self.i = self.i.wrapping_add(self.quirks.load_store_increment.amount(x));
         self.pc += 2;
         self.log("LD [I], Vx");
     }
@@ -471,10 +1866,29 @@ impl Chip8 {
         // LD Vx, [I]
         // Read registers V0 through Vx from memory starting at location I
         for i in 0..(x as u16) + 1 {
-            self.v[i as usize] = self.memory[(self.i + i) as usize];
+            self.v[i as usize] = self.read(self.i as usize + i as usize);
         }
+        self.i = self.i.wrapping_add(self.quirks.load_store_increment.amount(x));
         self.pc += 2;
         self.log("LD Vx, [I]");
     }
 
+    pub fn op_fx75(&mut self, x: usize) {
+        // LD R, Vx
+        // Store registers V0 through Vx in the RPL user flags (x <= 7)
+        let x = x.min(7);
+        self.rpl[..x + 1].copy_from_slice(&self.v[..x + 1]);
+        self.pc += 2;
+        self.log("LD R, Vx");
+    }
+
+    pub fn op_fx85(&mut self, x: usize) {
+        // LD Vx, R
+        // Read registers V0 through Vx from the RPL user flags (x <= 7)
+        let x = x.min(7);
+        self.v[..x + 1].copy_from_slice(&self.rpl[..x + 1]);
+        self.pc += 2;
+        self.log("LD Vx, R");
+    }
+
 }
@@ -0,0 +1,20 @@
+use std::collections::BTreeMap;
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_profile.rs"]
+mod test_profile;
+
+// instruction counts from Chip8::profile as CSV, most executed first
+pub fn to_csv(profile: &BTreeMap<(&'static str, &'static str), u64>) -> String {
+    let total: u64 = profile.values().sum();
+    let mut rows: Vec<_> = profile.iter().collect();
+    rows.sort_by(|a, b| b.1.cmp(a.1).then(a.0.cmp(b.0)));
+
+    let mut csv = String::from("class,mnemonic,count,percent\n");
+    for ((class, mnemonic), count) in rows {
+        let percent = *count as f64 * 100.0 / total as f64;
+        csv.push_str(&format!("{},{},{},{:.2}\n", class, mnemonic, count, percent));
+    }
+    csv
+}
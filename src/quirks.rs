@@ -0,0 +1,83 @@
+// behaviours that differ between CHIP-8 interpreters, and presets matching
+// the common platforms
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_quirks.rs"]
+mod test_quirks;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quirks {
+    pub logic_resets_vf:  bool,             // 8xy1/8xy2/8xy3 clear VF
+    pub load_store_increment: IncrementI,   // how far Fx55/Fx65 move I
+    pub jump_uses_vx:     bool,             // Bxnn jumps to xnn + Vx instead of nnn + V0
+    pub shift_uses_vy:    bool,             // 8xy6/8xyE shift Vy into Vx instead of Vx in place
+    pub wrap_x:           bool,             // Dxyn wraps sprites off the right edge instead of clipping
+    pub wrap_y:           bool,             // Dxyn wraps sprites off the bottom edge instead of clipping
+    pub fx1e_sets_vf:     bool,             // Fx1E sets VF when I passes 0xFFF, as on the Amiga interpreter
+    pub hires_switch_clears: bool,          // 00FE/00FF clear the display instead of rescaling it
+}
+
+// what Fx55/Fx65 do to I after the transfer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IncrementI {
+    Unchanged,                              // SUPER-CHIP 1.1 and most modern interpreters
+    ByX,                                    // CHIP-48 and SUPER-CHIP 1.0
+    ByXPlusOne,                             // COSMAC VIP and XO-CHIP: I ends past Vx
+}
+
+impl IncrementI {
+    pub fn amount(self, x: usize) -> u16 {
+        match self {
+            IncrementI::Unchanged  => 0,
+            IncrementI::ByX        => x as u16,
+            IncrementI::ByXPlusOne => x as u16 + 1,
+        }
+    }
+}
+
+// the defaults are the modern behaviour this emulator has always had,
+// including shifting Vx in place as SUPER-CHIP does
+impl Default for Quirks {
+    fn default() -> Self {
+        Preset::Modern.quirks()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Preset {
+    Cosmac,
+    Schip,
+    XoChip,
+    Modern,
+}
+
+impl Preset {
+
+    pub const ALL: [Preset; 4] = [Preset::Cosmac, Preset::Schip, Preset::XoChip, Preset::Modern];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Cosmac => "cosmac",
+            Preset::Schip  => "schip",
+            Preset::XoChip => "xochip",
+            Preset::Modern => "modern",
+        }
+    }
+
+    pub fn quirks(self) -> Quirks {
+        match self {
+            Preset::Cosmac => Quirks { logic_resets_vf: true,  load_store_increment: IncrementI::ByXPlusOne, jump_uses_vx: false, shift_uses_vy: true,  wrap_x: false, wrap_y: false, fx1e_sets_vf: false, hires_switch_clears: true  },
+            Preset::Schip  => Quirks { logic_resets_vf: false, load_store_increment: IncrementI::Unchanged,  jump_uses_vx: true,  shift_uses_vy: false, wrap_x: false, wrap_y: false, fx1e_sets_vf: false, hires_switch_clears: false },
+            Preset::XoChip => Quirks { logic_resets_vf: false, load_store_increment: IncrementI::ByXPlusOne, jump_uses_vx: false, shift_uses_vy: true,  wrap_x: true,  wrap_y: true,  fx1e_sets_vf: false, hires_switch_clears: true  },
+            Preset::Modern => Quirks { logic_resets_vf: false, load_store_increment: IncrementI::Unchanged,  jump_uses_vx: false, shift_uses_vy: false, wrap_x: true,  wrap_y: true,  fx1e_sets_vf: false, hires_switch_clears: true  },
+        }
+    }
+
+    // the neighbouring preset, wrapping around; `step` is 1 or -1
+    pub fn cycle(self, step: isize) -> Preset {
+        let n = Preset::ALL.len() as isize;
+        let index = Preset::ALL.iter().position(|&preset| preset == self).unwrap() as isize;
+        Preset::ALL[(index + step).rem_euclid(n) as usize]
+    }
+}
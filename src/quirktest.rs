@@ -0,0 +1,81 @@
+use crate::processor::Chip8;
+use crate::quirks::Preset;
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_quirktest.rs"]
+mod test_quirktest;
+
+// reads the results screen of the quirks test in the combined ROM of
+// Timendus' chip8-test-suite. the ROM normally opens on a menu of tests and
+// the quirks test then asks which platform to check against; a test number
+// already at TEST_ADDR and a platform number at PLATFORM_ADDR skip both.
+// once it finishes it lists one quirk per 5 pixel line of text, each with
+// ON or OFF and a tick or a cross at MARKER_X
+
+pub const TEST_ADDR: usize = 0x1FF;
+pub const QUIRKS_TEST: u8 = 4;
+pub const PLATFORM_ADDR: usize = 0x1FE;
+
+pub const MARKER_X: usize = 59;
+
+// (quirk, top row of its line)
+const ROWS: [(&str, usize); 6] = [
+    ("vF reset",     1),
+    ("Memory",       6),
+    ("Display wait", 11),
+    ("Clipping",     16),
+    ("Shifting",     21),
+    ("Jumping",      26),
+];
+
+// 3x3 marker glyphs a row below the top of the line, one byte per row,
+// leftmost pixel in the high bit
+pub const TICK:  [u8; 3] = [0xA0, 0xC0, 0x80];
+pub const CROSS: [u8; 3] = [0xA0, 0x40, 0xA0];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Outcome {
+    Pass,
+    Fail,
+    Unknown,                                // no marker, or not finished yet
+}
+
+// the platform the ROM should check a preset's quirks against
+pub fn platform(preset: Preset) -> u8 {
+    match preset {
+        Preset::Cosmac | Preset::Modern => 1,   // CHIP-8
+        Preset::Schip  => 2,                    // SUPER-CHIP
+        Preset::XoChip => 3,                    // XO-CHIP
+    }
+}
+
+pub fn read(my_chip8: &Chip8) -> Vec<(&'static str, Outcome)> {
+    ROWS.iter().map(|&(quirk, top)| {
+        let mut marker = [0u8; 3];
+        for (row, bits) in marker.iter_mut().enumerate() {
+            for col in 0..3 {
+                if my_chip8.pixel(MARKER_X + col, top + 1 + row) != 0 {
+                    *bits |= 0x80 >> col;
+                }
+            }
+        }
+        let outcome = match marker {
+            TICK => Outcome::Pass,
+            CROSS => Outcome::Fail,
+            _ => Outcome::Unknown,
+        };
+        (quirk, outcome)
+    }).collect()
+}
+
+pub fn summary(results: &[(&str, Outcome)]) -> String {
+    results.iter().map(|(quirk, outcome)| {
+        let outcome = match outcome {
+            Outcome::Pass => "pass",
+            Outcome::Fail => "FAIL",
+            Outcome::Unknown => "no result",
+        };
+        format!("{:<14}{}\n", quirk, outcome)
+    }).collect()
+}
@@ -0,0 +1,117 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::processor::{Chip8, Snapshot};
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_replay.rs"]
+mod test_replay;
+
+// how often the timeline keeps a snapshot to seek from, in frames
+pub const KEYFRAME_INTERVAL: usize = 60;
+
+// the keys held in each 60Hz frame of a session, one bit per key, and the
+// seed the session ran with. saved as text: a "seed <n>" line, then one hex
+// key mask per frame
+#[derive(Debug, PartialEq)]
+pub struct Recording {
+    pub seed:   u64,
+    pub frames: Vec<u16>,
+}
+
+impl Recording {
+
+    pub fn new(seed: u64) -> Self {
+        Self { seed, frames: Vec::new() }
+    }
+
+    pub fn push(&mut self, key: &[bool; 16]) {
+        let mask = key.iter().enumerate().fold(0, |mask, (n, &held)| mask | (held as u16) << n);
+        self.frames.push(mask);
+    }
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines();
+        let seed = lines.next()
+            .and_then(|line| line.strip_prefix("seed "))
+            .and_then(|seed| seed.parse().ok())
+            .ok_or("missing seed line")?;
+        let frames = lines
+            .enumerate()
+            .map(|(n, line)| u16::from_str_radix(line, 16).map_err(|_| format!("frame {}: invalid key mask {}", n, line)))
+            .collect::<Result<_, _>>()?;
+        Ok(Self { seed, frames })
+    }
+
+    pub fn load(path: &Path) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self::parse(&fs::read_to_string(path)?)?)
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        let mut text = format!("seed {}\n", self.seed);
+        for mask in self.frames.iter() {
+            text.push_str(&format!("{:04x}\n", mask));
+        }
+        fs::write(path, text)
+    }
+}
+
+// one frame of a replay: the recorded keys, a frame's worth of instructions,
+// then the timers. this is the same per-frame work as the windowed run
+// loop, so recorded sessions replay exactly
+pub fn run_frame(my_chip8: &mut Chip8, mask: u16) {
+    for (n, held) in my_chip8.key.iter_mut().enumerate() {
+        *held = mask & (1 << n) != 0;
+    }
+    my_chip8.run_cycles(my_chip8.cycles_per_frame());
+    if !my_chip8.cycle_timers {
+        my_chip8.tick_timers();
+    }
+}
+
+// a recording played through once, keeping a snapshot every
+// KEYFRAME_INTERVAL frames so any frame can be rebuilt from the nearest one
+// before it
+pub struct Timeline {
+    pub recording: Recording,
+    keyframes:     Vec<Snapshot>,
+}
+
+impl Timeline {
+
+    // `my_chip8` holds the loaded ROM at the start of the session and is
+    // left at its end
+    pub fn build(my_chip8: &mut Chip8, recording: Recording) -> Self {
+        my_chip8.seed(recording.seed);
+        let mut keyframes = Vec::new();
+        for (frame, &mask) in recording.frames.iter().enumerate() {
+            if frame % KEYFRAME_INTERVAL == 0 {
+                keyframes.push(my_chip8.rewind_state());
+            }
+            run_frame(my_chip8, mask);
+        }
+        // seeking to the very end needs a keyframe there when the recording
+        // ends on an interval
+        if keyframes.len() == recording.frames.len() / KEYFRAME_INTERVAL {
+            keyframes.push(my_chip8.rewind_state());
+        }
+        Self { recording, keyframes }
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.recording.frames.len()
+    }
+
+    // put the machine in its state at the start of `frame`, clamped to the
+    // end of the recording, and return the frame reached
+    pub fn seek(&self, my_chip8: &mut Chip8, frame: usize) -> usize {
+        let frame = frame.min(self.frame_count());
+        let keyframe = frame / KEYFRAME_INTERVAL;
+        my_chip8.restore(&self.keyframes[keyframe]);
+        for &mask in self.recording.frames[keyframe * KEYFRAME_INTERVAL..frame].iter() {
+            run_frame(my_chip8, mask);
+        }
+        frame
+    }
+}
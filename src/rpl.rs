@@ -0,0 +1,48 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_rpl.rs"]
+mod test_rpl;
+
+// RPL user flags are kept per ROM, one 8-byte file named after the ROM hash,
+// so high scores written with Fx75 survive restarts
+
+// $XDG_DATA_HOME/chip8, falling back to ~/.local/share/chip8, %APPDATA%\chip8
+// and finally the working directory
+pub fn data_dir() -> PathBuf {
+    if let Some(dir) = env::var_os("XDG_DATA_HOME") {
+        return PathBuf::from(dir).join("chip8");
+    }
+    if let Some(home) = env::var_os("HOME") {
+        return PathBuf::from(home).join(".local/share/chip8");
+    }
+    if let Some(dir) = env::var_os("APPDATA") {
+        return PathBuf::from(dir).join("chip8");
+    }
+    PathBuf::from(".")
+}
+
+fn flags_path(dir: &Path, rom_hash: u64) -> PathBuf {
+    dir.join(format!("{:016x}.rpl", rom_hash))
+}
+
+// flags saved for this ROM, or all zeros if there are none yet
+pub fn load(dir: &Path, rom_hash: u64) -> [u8; 8] {
+    let mut flags = [0; 8];
+    match fs::read(flags_path(dir, rom_hash)) {
+        Ok(data) if data.len() == flags.len() => flags.copy_from_slice(&data),
+        Ok(data) => log::warn!("ignoring RPL flags file of {} bytes", data.len()),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => {}
+        Err(err) => log::warn!("could not read RPL flags: {}", err),
+    }
+    flags
+}
+
+pub fn save(dir: &Path, rom_hash: u64, flags: &[u8; 8]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(flags_path(dir, rom_hash), flags)
+}
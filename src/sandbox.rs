@@ -0,0 +1,89 @@
+use std::fmt;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+use crate::processor::Chip8;
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_sandbox.rs"]
+mod test_sandbox;
+
+#[derive(Debug, PartialEq)]
+pub enum SandboxError {
+    RomTooLarge { size: usize, max: usize },
+    CycleLimit(usize),
+    Timeout(Duration),
+    Panicked,
+}
+
+impl fmt::Display for SandboxError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SandboxError::RomTooLarge { size, max } => write!(f, "ROM is {} bytes, at most {} fit in memory", size, max),
+            SandboxError::CycleLimit(cycles) => write!(f, "ROM did not exit within {} cycles", cycles),
+            SandboxError::Timeout(timeout) => write!(f, "ROM did not exit within {:?}", timeout),
+            SandboxError::Panicked => write!(f, "emulation thread panicked"),
+        }
+    }
+}
+
+impl std::error::Error for SandboxError {}
+
+// run an untrusted ROM on its own thread until it exits with 00FD, giving up
+// after `max_cycles` instructions or `timeout` of wall time; on success the
+// display is returned as one byte per pixel, row by row, at whichever
+// resolution the ROM left it in
+pub fn run_sandboxed(rom: &[u8], max_cycles: usize, timeout: Duration) -> Result<Vec<u8>, SandboxError> {
+    let max = 4096 - 0x200;
+    if rom.len() > max {
+        return Err(SandboxError::RomTooLarge { size: rom.len(), max });
+    }
+
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.memory[0x200..0x200 + rom.len()].copy_from_slice(rom);
+
+    let cancel = Arc::new(AtomicBool::new(false));
+    let worker_cancel = Arc::clone(&cancel);
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for _ in 0..max_cycles {
+            if my_chip8.exited || worker_cancel.load(Ordering::Relaxed) {
+                break;
+            }
+            my_chip8.emulate_cycle();
+        }
+
+        let result = if my_chip8.exited {
+            Ok(display(&my_chip8))
+        } else {
+            Err(SandboxError::CycleLimit(max_cycles))
+        };
+        let _ = sender.send(result);
+    });
+
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(RecvTimeoutError::Timeout) => {
+            cancel.store(true, Ordering::Relaxed);
+            Err(SandboxError::Timeout(timeout))
+        }
+        // the sender is only dropped without sending if the thread panicked
+        Err(RecvTimeoutError::Disconnected) => Err(SandboxError::Panicked),
+    }
+}
+
+fn display(my_chip8: &Chip8) -> Vec<u8> {
+    let (width, height) = my_chip8.resolution();
+    let mut out = Vec::with_capacity((width * height) as usize);
+    for y in 0..height as usize {
+        for x in 0..width as usize {
+            out.push(my_chip8.pixel(x, y));
+        }
+    }
+    out
+}
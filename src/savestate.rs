@@ -0,0 +1,87 @@
+use bincode::Options;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_big_array::Array;
+use crate::processor::{Chip8, Chip8Error, Snapshot};
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_savestate.rs"]
+mod test_savestate;
+
+// save states: a Snapshot encoded with bincode. the layout follows the
+// Snapshot fields, so a state only loads into a build with the same ones
+
+fn options() -> impl Options {
+    bincode::DefaultOptions::new().reject_trailing_bytes()
+}
+
+pub fn encode(snapshot: &Snapshot) -> Vec<u8> {
+    // serializing into memory can't fail
+    options().serialize(snapshot).unwrap()
+}
+
+pub fn decode(data: &[u8]) -> Result<Snapshot, Chip8Error> {
+    let snapshot: Snapshot = options().deserialize(data).map_err(|err| match *err {
+        bincode::ErrorKind::Io(_) => Chip8Error::BadState("truncated"),
+        _ => Chip8Error::BadState("not a save state"),
+    })?;
+    if snapshot.sp > snapshot.stack.len() {
+        return Err(Chip8Error::BadState("stack pointer out of range"));
+    }
+    Ok(snapshot)
+}
+
+// the display is an array of arrays, both longer than serde handles, so it
+// goes row by row through serde_big_array's wrapper
+pub(crate) mod gfx {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(gfx: &[[u8; 128]; 64], serializer: S) -> Result<S::Ok, S::Error> {
+        Array(gfx.map(Array)).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<[[u8; 128]; 64], D::Error> {
+        let rows: Array<Array<u8, 128>, 64> = Deserialize::deserialize(deserializer)?;
+        Ok(rows.0.map(|row| row.0))
+    }
+}
+
+// a machine serializes as its save state bytes, and deserializes into a
+// fresh machine restored from them
+
+impl Serialize for Chip8 {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_bytes(&self.save_state())
+    }
+}
+
+impl<'de> Deserialize<'de> for Chip8 {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        struct StateVisitor;
+
+        impl<'de> serde::de::Visitor<'de> for StateVisitor {
+            type Value = Chip8;
+
+            fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+                write!(f, "chip8 save state bytes")
+            }
+
+            fn visit_bytes<E: serde::de::Error>(self, data: &[u8]) -> Result<Chip8, E> {
+                let mut my_chip8 = Chip8::initialize();
+                my_chip8.load_state(data).map_err(E::custom)?;
+                Ok(my_chip8)
+            }
+
+            // formats without a bytes type, like JSON, hand over a sequence
+            fn visit_seq<A: serde::de::SeqAccess<'de>>(self, mut seq: A) -> Result<Chip8, A::Error> {
+                let mut data = Vec::new();
+                while let Some(byte) = seq.next_element()? {
+                    data.push(byte);
+                }
+                self.visit_bytes(&data)
+            }
+        }
+
+        deserializer.deserialize_bytes(StateVisitor)
+    }
+}
@@ -0,0 +1,33 @@
+use std::collections::BTreeMap;
+
+// address to source line map for ROMs built from Octo source, one entry per
+// line: a hex address (0x optional), whitespace, then the source text
+//
+//     0x200 : main
+//     0x202 v0 := 5
+
+pub struct SourceMap {
+    lines: BTreeMap<u16, String>,
+}
+
+impl SourceMap {
+
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut lines = BTreeMap::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (addr, source) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let addr = u16::from_str_radix(addr.trim_start_matches("0x"), 16)
+                .map_err(|_| format!("line {}: invalid address {}", number + 1, addr))?;
+            lines.insert(addr, source.trim().to_string());
+        }
+        Ok(Self { lines })
+    }
+
+    pub fn line(&self, addr: u16) -> Option<&str> {
+        self.lines.get(&addr).map(String::as_str)
+    }
+}
@@ -0,0 +1,73 @@
+use std::env;
+use std::fs;
+use crate::audio::{pattern_rate, Buzzer, Recorder, Tone, ToneGate, DEFAULT_PITCH, SAMPLE_RATE};
+#[cfg(feature = "sound")]
+use crate::audio::SharedTone;
+
+#[test]
+fn test_record_beep() {
+    let path = env::temp_dir().join(format!("chip8-beep-{}.wav", std::process::id()));
+
+    // half a second of tone, then half a second of silence
+    let mut recorder = Recorder::create(&path).unwrap();
+    for frame in 0..60 {
+        recorder.frame(if frame < 30 { Tone::Buzzer } else { Tone::Silent }).unwrap();
+    }
+    recorder.finish().unwrap();
+
+    let reader = hound::WavReader::open(&path).unwrap();
+    assert_eq!(reader.spec().sample_rate, SAMPLE_RATE);
+    assert_eq!(reader.duration(), SAMPLE_RATE);
+
+    let samples: Vec<i16> = reader.into_samples().map(Result::unwrap).collect();
+    assert!(samples[..SAMPLE_RATE as usize / 2].iter().any(|&s| s != 0));
+    assert!(samples[SAMPLE_RATE as usize / 2..].iter().all(|&s| s == 0));
+
+    fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_tone_gate_reports_transitions() {
+    let mut gate = ToneGate::new();
+    assert_eq!(gate.update(false), None);
+    assert_eq!(gate.update(true), Some(true));
+    assert_eq!(gate.update(true), None);
+    assert_eq!(gate.update(true), None);
+    assert_eq!(gate.update(false), Some(false));
+    assert_eq!(gate.update(false), None);
+}
+
+#[test]
+fn test_pattern_playback() {
+    assert_eq!(pattern_rate(DEFAULT_PITCH), 4000.0);
+    assert_eq!(pattern_rate(DEFAULT_PITCH + 48), 8000.0);
+
+    // 64 bits high then 64 low, at 4000 bits a second: high for the first
+    // 64 / 4000 of a second, about 705 samples
+    let mut bits = [0x00; 16];
+    bits[..8].copy_from_slice(&[0xFF; 8]);
+    let mut buzzer = Buzzer::new(SAMPLE_RATE);
+    let samples = buzzer.frame(Tone::Pattern { bits, rate: pattern_rate(DEFAULT_PITCH) });
+    assert_eq!(samples.len(), SAMPLE_RATE as usize / 60);
+    assert!(samples[..700].iter().all(|&s| s > 0));
+    assert!(samples[710..].iter().all(|&s| s < 0));
+
+    assert!(buzzer.frame(Tone::Silent).iter().all(|&s| s == 0));
+}
+
+#[cfg(feature = "sound")]
+#[test]
+fn test_shared_tone_round_trip() {
+    let shared = SharedTone::default();
+    assert_eq!(shared.load(), Tone::Silent);
+
+    let mut bits = [0; 16];
+    for (n, byte) in bits.iter_mut().enumerate() {
+        *byte = n as u8 * 17;
+    }
+    let tones = [Tone::Buzzer, Tone::Pattern { bits, rate: pattern_rate(100) }, Tone::Silent];
+    for &tone in tones.iter() {
+        shared.store(tone);
+        assert_eq!(shared.load(), tone);
+    }
+}
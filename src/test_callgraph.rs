@@ -0,0 +1,25 @@
+use std::collections::BTreeSet;
+use crate::callgraph::to_dot;
+use crate::Chip8;
+
+#[test]
+fn test_callgraph_edges() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.call_edges = Some(BTreeSet::new());
+    my_chip8.memory[0x200..0x20c].copy_from_slice(&[
+        0x22, 0x06, // 0x200: CALL 0x206
+        0x22, 0x0a, // 0x202: CALL 0x20a
+        0x12, 0x04, // 0x204: JP 0x204
+        0x22, 0x0a, // 0x206: CALL 0x20a
+        0x00, 0xee, // 0x208: RET
+        0x00, 0xee, // 0x20a: RET
+    ]);
+    for _ in 0..8 {
+        my_chip8.emulate_cycle();
+    }
+
+    assert_eq!(
+        to_dot(my_chip8.call_edges.as_ref().unwrap()),
+        "digraph calls {\n    \"0x200\" -> \"0x206\";\n    \"0x202\" -> \"0x20a\";\n    \"0x206\" -> \"0x20a\";\n}\n"
+    );
+}
@@ -0,0 +1,43 @@
+use crate::disasm::{disassemble, to_text};
+
+#[test]
+fn test_disassemble_operands() {
+    let rom = [
+        0x00, 0xE0, // CLS
+        0x6A, 0x02, // LD VA, 0x02
+        0xA2, 0x1E, // LD I, 0x21e
+        0xD0, 0x15, // DRW V0, V1, 5
+        0x8A, 0xB4, // ADD VA, VB
+        0xF3, 0x65, // LD V3, [I]
+        0x12, 0x00, // JP 0x200
+        0xF0, 0x00, // LD I, 0x1234
+        0x12, 0x34, // the address, not JP 0x234
+        0xFF, 0xFF, // not an instruction
+    ];
+    let texts: Vec<String> = disassemble(&rom, 0x200).into_iter().map(|(_, _, text)| text).collect();
+    assert_eq!(texts, [
+        "CLS",
+        "LD VA, 0x02",
+        "LD I, 0x21e",
+        "DRW V0, V1, 5",
+        "ADD VA, VB",
+        "LD V3, [I]",
+        "JP 0x200",
+        "LD I, 0x1234",
+        "DW 0x1234",
+        "???",
+    ]);
+}
+
+#[test]
+fn test_disassemble_addresses() {
+    let listing = disassemble(&[0x6A, 0x02, 0x12, 0x00, 0x42], 0x200);
+    assert_eq!(listing[1], (0x202, 0x1200, "JP 0x200".to_string()));
+    // a trailing odd byte is data, not half an instruction
+    assert_eq!(listing[2], (0x204, 0x42, "DB 0x42".to_string()));
+    assert_eq!(to_text(&listing[..1]), "0x200  6A02  LD VA, 0x02\n");
+
+    // an F000 at the end has no address to show
+    let listing = disassemble(&[0xF0, 0x00], 0x200);
+    assert_eq!(listing[0].2, "LD I, long");
+}
@@ -0,0 +1,58 @@
+use crate::Chip8;
+use crate::framelog::{parse, record, to_text, verify};
+
+// redraws a digit counting up, waiting a frame on the delay timer between
+// draws
+const COUNTER: [u8; 24] = [
+    0x00, 0xE0,                             // 200: CLS
+    0xF0, 0x29,                             // 202: LD F, V0
+    0xD1, 0x15,                             // 204: DRW V1, V1, 5
+    0x70, 0x01,                             // 206: ADD V0, 1
+    0x62, 0x01,                             // 208: LD V2, 1
+    0xF2, 0x15,                             // 20A: LD DT, V2
+    0xF2, 0x07,                             // 20C: LD V2, DT
+    0x32, 0x00,                             // 20E: SE V2, 0
+    0x12, 0x0C,                             // 210: JP 20C
+    0x40, 0x10,                             // 212: SNE V0, 16
+    0x60, 0x00,                             // 214: LD V0, 0
+    0x12, 0x00,                             // 216: JP 200
+];
+
+fn counter(rom: &[u8]) -> Chip8 {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.memory[0x200..0x200 + rom.len()].copy_from_slice(rom);
+    my_chip8.seed(0);
+    my_chip8.cycle_timers = true;
+    my_chip8
+}
+
+#[test]
+fn test_record_then_verify() {
+    let reference = record(&mut counter(&COUNTER), 500);
+    assert!(reference.len() >= 5);
+    // the display changes during the run
+    assert!(reference.windows(2).any(|pair| pair[0] != pair[1]));
+
+    let reference = parse(&to_text(&reference)).unwrap();
+    assert_eq!(verify(&mut counter(&COUNTER), &reference), Ok(()));
+}
+
+#[test]
+fn test_verify_reports_first_mismatch() {
+    let reference = record(&mut counter(&COUNTER), 500);
+
+    // count by two instead: the first frame still shows 0, the next differs
+    let mut rom = COUNTER;
+    rom[7] = 0x02;
+    let mismatch = verify(&mut counter(&rom), &reference).unwrap_err();
+    assert_eq!(mismatch.frame, 1);
+    assert_eq!(mismatch.expected, reference[1]);
+    assert_ne!(mismatch.actual, reference[1]);
+    assert!(mismatch.to_string().starts_with("frame 1 differs"));
+}
+
+#[test]
+fn test_parse_rejects_bad_hash() {
+    assert_eq!(parse("00000000000000FF\nxyz\n"), Err("frame 1: invalid hash xyz".to_string()));
+}
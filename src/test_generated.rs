@@ -0,0 +1,423 @@
+// generated by --gen-tests: one test per instruction class, replaying an
+// execution captured from a real run
+
+use crate::Chip8;
+use crate::quirks::Preset;
+
+// lit pixels as (x, y), column by column
+fn lit(my_chip8: &Chip8) -> Vec<(usize, usize)> {
+    (0..64).flat_map(|x| (0..32).map(move |y| (x, y))).filter(|&(x, y)| my_chip8.pixel(x, y) != 0).collect()
+}
+
+#[test]
+fn test_gen_00e0() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x200;
+    my_chip8.memory[0x200..0x202].copy_from_slice(&[0x00, 0xe0]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x202);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+    assert_eq!(lit(&my_chip8), Vec::<(usize, usize)>::new());
+}
+
+#[test]
+fn test_gen_6xkk() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x202;
+    my_chip8.memory[0x202..0x204].copy_from_slice(&[0x6a, 0x05]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x204);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_7xkk() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x206;
+    my_chip8.memory[0x206..0x208].copy_from_slice(&[0x7a, 0x03]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x05, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x208);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_8xy0() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x208;
+    my_chip8.memory[0x208..0x20a].copy_from_slice(&[0x8c, 0xa0]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x20a);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x08, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_8xy1() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x20a;
+    my_chip8.memory[0x20a..0x20c].copy_from_slice(&[0x8c, 0xb1]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x08, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x20c);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x0c, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_8xy2() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x20c;
+    my_chip8.memory[0x20c..0x20e].copy_from_slice(&[0x8c, 0xb2]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x0c, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x20e);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x0c, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_8xy3() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x20e;
+    my_chip8.memory[0x20e..0x210].copy_from_slice(&[0x8c, 0xb3]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x0c, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x210);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_8xy4() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x210;
+    my_chip8.memory[0x210..0x212].copy_from_slice(&[0x8a, 0xb4]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x212);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x14, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_8xy5() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x212;
+    my_chip8.memory[0x212..0x214].copy_from_slice(&[0x8a, 0xb5]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x14, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x214);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x00, 0x00, 0x00, 0x01]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_8xy6() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x214;
+    my_chip8.memory[0x214..0x216].copy_from_slice(&[0x8a, 0x06]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x00, 0x00, 0x00, 0x01];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x216);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_8xy7() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x216;
+    my_chip8.memory[0x216..0x218].copy_from_slice(&[0x8a, 0xb7]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x04, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x218);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x00, 0x00, 0x00, 0x01]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_8xye() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x218;
+    my_chip8.memory[0x218..0x21a].copy_from_slice(&[0x8a, 0x0e]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x08, 0x0c, 0x00, 0x00, 0x00, 0x01];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x21a);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_3xkk() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x21a;
+    my_chip8.memory[0x21a..0x21c].copy_from_slice(&[0x3a, 0x10]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x21e);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_4xkk() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x21e;
+    my_chip8.memory[0x21e..0x220].copy_from_slice(&[0x4a, 0x10]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x220);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_5xy0() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x220;
+    my_chip8.memory[0x220..0x222].copy_from_slice(&[0x5a, 0xb0]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x222);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_9xy0() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x222;
+    my_chip8.memory[0x222..0x224].copy_from_slice(&[0x9a, 0xb0]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x226);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x000);
+}
+
+#[test]
+fn test_gen_annn() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x226;
+    my_chip8.memory[0x226..0x228].copy_from_slice(&[0xa3, 0x00]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x000;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x228);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x300);
+}
+
+#[test]
+fn test_gen_fx33() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x228;
+    my_chip8.memory[0x228..0x22a].copy_from_slice(&[0xfa, 0x33]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x300;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x22a);
+    assert_eq!(my_chip8.v, [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x300);
+    assert_eq!(my_chip8.memory[0x301..0x303], [0x01, 0x06]);
+}
+
+#[test]
+fn test_gen_fx65() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x22a;
+    my_chip8.memory[0x22a..0x22c].copy_from_slice(&[0xf2, 0x65]);
+    my_chip8.memory[0x300..0x303].copy_from_slice(&[0x00, 0x01, 0x06]);
+    my_chip8.v = [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x300;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x22c);
+    assert_eq!(my_chip8.v, [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x300);
+}
+
+#[test]
+fn test_gen_fx29() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x22c;
+    my_chip8.memory[0x22c..0x22e].copy_from_slice(&[0xf2, 0x29]);
+    my_chip8.v = [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x300;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x22e);
+    assert_eq!(my_chip8.v, [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x01e);
+}
+
+#[test]
+fn test_gen_dxyn() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x22e;
+    my_chip8.memory[0x22e..0x230].copy_from_slice(&[0xd0, 0x15]);
+    my_chip8.memory[0x01e..0x023].copy_from_slice(&[0xf0, 0x80, 0xf0, 0x90, 0xf0]);
+    my_chip8.v = [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x01e;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x230);
+    assert_eq!(my_chip8.v, [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x01e);
+    assert_eq!(lit(&my_chip8), vec![(0, 1), (0, 2), (0, 3), (0, 4), (0, 5), (1, 1), (1, 3), (1, 5), (2, 1), (2, 3), (2, 5), (3, 1), (3, 3), (3, 4), (3, 5)]);
+}
+
+#[test]
+fn test_gen_2nnn() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x230;
+    my_chip8.memory[0x230..0x232].copy_from_slice(&[0x22, 0x40]);
+    my_chip8.v = [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00];
+    my_chip8.i = 0x01e;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x240);
+    assert_eq!(my_chip8.v, [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x00, 0x00]);
+    assert_eq!(my_chip8.i, 0x01e);
+    assert_eq!(my_chip8.sp, 1);
+    assert_eq!(my_chip8.stack[..1], [0x232]);
+}
+
+#[test]
+fn test_gen_fx55() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x242;
+    my_chip8.memory[0x242..0x244].copy_from_slice(&[0xfe, 0x55]);
+    my_chip8.v = [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00];
+    my_chip8.i = 0x01e;
+    my_chip8.sp = 1;
+    my_chip8.stack[..1].copy_from_slice(&[0x232]);
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x244);
+    assert_eq!(my_chip8.v, [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00]);
+    assert_eq!(my_chip8.i, 0x01e);
+    assert_eq!(my_chip8.sp, 1);
+    assert_eq!(my_chip8.stack[..1], [0x232]);
+    assert_eq!(my_chip8.memory[0x01f..0x02d], [0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01]);
+}
+
+#[test]
+fn test_gen_00ee() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x244;
+    my_chip8.memory[0x244..0x246].copy_from_slice(&[0x00, 0xee]);
+    my_chip8.v = [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00];
+    my_chip8.i = 0x01e;
+    my_chip8.sp = 1;
+    my_chip8.stack[..1].copy_from_slice(&[0x232]);
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x232);
+    assert_eq!(my_chip8.v, [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00]);
+    assert_eq!(my_chip8.i, 0x01e);
+    assert_eq!(my_chip8.sp, 0);
+}
+
+#[test]
+fn test_gen_fx1e() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x232;
+    my_chip8.memory[0x232..0x234].copy_from_slice(&[0xf0, 0x1e]);
+    my_chip8.v = [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00];
+    my_chip8.i = 0x01e;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x234);
+    assert_eq!(my_chip8.v, [0x00, 0x01, 0x06, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00]);
+    assert_eq!(my_chip8.i, 0x01e);
+}
+
+#[test]
+fn test_gen_fx15() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x236;
+    my_chip8.memory[0x236..0x238].copy_from_slice(&[0xf2, 0x15]);
+    my_chip8.v = [0x00, 0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00];
+    my_chip8.i = 0x01e;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x238);
+    assert_eq!(my_chip8.v, [0x00, 0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00]);
+    assert_eq!(my_chip8.i, 0x01e);
+    assert_eq!(my_chip8.delay_timer, 5);
+}
+
+#[test]
+fn test_gen_fx07() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x238;
+    my_chip8.memory[0x238..0x23a].copy_from_slice(&[0xf3, 0x07]);
+    my_chip8.v = [0x00, 0x01, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00];
+    my_chip8.i = 0x01e;
+    my_chip8.delay_timer = 5;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x23a);
+    assert_eq!(my_chip8.v, [0x00, 0x01, 0x05, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00]);
+    assert_eq!(my_chip8.i, 0x01e);
+}
+
+#[test]
+fn test_gen_fx18() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x23a;
+    my_chip8.memory[0x23a..0x23c].copy_from_slice(&[0xf2, 0x18]);
+    my_chip8.v = [0x00, 0x01, 0x05, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00];
+    my_chip8.i = 0x01e;
+    my_chip8.delay_timer = 5;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x23c);
+    assert_eq!(my_chip8.v, [0x00, 0x01, 0x05, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00]);
+    assert_eq!(my_chip8.i, 0x01e);
+    assert_eq!(my_chip8.sound_timer, 5);
+}
+
+#[test]
+fn test_gen_bnnn() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x23c;
+    my_chip8.memory[0x23c..0x23e].copy_from_slice(&[0xb2, 0x3e]);
+    my_chip8.v = [0x00, 0x01, 0x05, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00];
+    my_chip8.i = 0x01e;
+    my_chip8.delay_timer = 5;
+    my_chip8.sound_timer = 5;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x23e);
+    assert_eq!(my_chip8.v, [0x00, 0x01, 0x05, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00]);
+    assert_eq!(my_chip8.i, 0x01e);
+}
+
+#[test]
+fn test_gen_1nnn() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Modern.quirks());
+    my_chip8.pc = 0x23e;
+    my_chip8.memory[0x23e..0x240].copy_from_slice(&[0x12, 0x3e]);
+    my_chip8.v = [0x00, 0x01, 0x05, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00];
+    my_chip8.i = 0x01e;
+    my_chip8.delay_timer = 5;
+    my_chip8.sound_timer = 5;
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x23e);
+    assert_eq!(my_chip8.v, [0x00, 0x01, 0x05, 0x05, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x0c, 0x00, 0x00, 0x01, 0x00]);
+    assert_eq!(my_chip8.i, 0x01e);
+}
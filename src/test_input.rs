@@ -0,0 +1,46 @@
+use winit::keyboard::KeyCode;
+use crate::input::{InputDelay, Keymap};
+
+#[test]
+fn test_input_delay() {
+    let mut keys = [false; 16];
+    let mut delay = InputDelay::new(2);
+
+    delay.push(0x5, true);
+    delay.apply(&mut keys);
+    assert!(!keys[0x5]);
+
+    delay.tick(&mut keys);
+    assert!(!keys[0x5]);
+    delay.tick(&mut keys);
+    assert!(keys[0x5]);
+}
+
+#[test]
+fn test_no_input_delay() {
+    let mut keys = [false; 16];
+    let mut delay = InputDelay::new(0);
+
+    delay.push(0x5, true);
+    delay.apply(&mut keys);
+    assert!(keys[0x5]);
+}
+
+#[test]
+fn test_default_keymap() {
+    let keymap = Keymap::default();
+    assert_eq!(keymap.binds().len(), 16);
+    assert!(keymap.binds().contains(&(KeyCode::KeyX, 0x0)));
+    assert!(keymap.binds().contains(&(KeyCode::KeyV, 0xF)));
+}
+
+#[test]
+fn test_keymap_parse() {
+    let keymap = Keymap::parse("# azerty\nKeyA = 4\n\nKeyZ=5  # second\nKeyW = a\n").unwrap();
+    assert_eq!(keymap.binds(), [(KeyCode::KeyA, 0x4), (KeyCode::KeyZ, 0x5), (KeyCode::KeyW, 0xA)]);
+    assert_eq!(Keymap::from_pairs(&[(KeyCode::KeyA, 4), (KeyCode::KeyB, 16)]).binds(), [(KeyCode::KeyA, 4)]);
+
+    assert!(Keymap::parse("KeyA = 10").is_err());
+    assert!(Keymap::parse("Nope = 1").is_err());
+    assert_eq!(Keymap::parse("KeyA 1").err().unwrap(), "keymap line 1: expected `KeyName = 0-F`, got `KeyA 1`");
+}
@@ -0,0 +1,40 @@
+use std::io::{Read, Write};
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use crate::events::Event;
+use crate::metrics::{serve, FpsCounter, Metrics};
+
+#[test]
+fn test_metrics_endpoint() {
+    let metrics = Arc::new(Metrics::default());
+    metrics.instructions.store(1234, Ordering::Relaxed);
+    metrics.event(Event::Collision { x: 0, y: 0 });
+    metrics.event(Event::SoundStart);
+    metrics.event(Event::SoundStop);
+
+    let addr = serve(0, metrics.clone()).unwrap();
+    let mut stream = TcpStream::connect(("127.0.0.1", addr.port())).unwrap();
+    stream.write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n").unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200 OK\r\n"));
+    for name in ["chip8_instructions_total", "chip8_frames_total", "chip8_fps", "chip8_collisions_total", "chip8_beeps_total"].iter() {
+        assert!(response.contains(&format!("# TYPE {} ", name)), "missing {}", name);
+    }
+    assert!(response.contains("\nchip8_instructions_total 1234\n"));
+    assert!(response.contains("\nchip8_collisions_total 1\n"));
+    assert!(response.contains("\nchip8_beeps_total 1\n"));
+}
+
+#[test]
+fn test_fps_counter() {
+    let start = Instant::now();
+    let mut fps = FpsCounter::new(start);
+    for frame in 1..30 {
+        assert_eq!(fps.frame(start + Duration::from_millis(frame * 20)), None);
+    }
+    assert_eq!(fps.frame(start + Duration::from_secs(1)), Some(30.0));
+}
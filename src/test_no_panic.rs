@@ -0,0 +1,47 @@
+use proptest::prelude::*;
+use crate::Chip8;
+use crate::processor::Chip8Error;
+use crate::instruction::DecodedInstruction;
+use crate::quirks::Preset;
+use crate::{WIDTH, HEIGHT};
+
+// no ROM, however malformed, and no sequence of key presses may panic the
+// emulator; faults stop it with an error instead
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn test_arbitrary_rom_never_panics(
+        rom in proptest::collection::vec(any::<u16>(), 0..1792),
+        keys in proptest::collection::vec(any::<u16>(), 1..64),
+        preset in 0..Preset::ALL.len(),
+        seed in any::<u64>(),
+    ) {
+        let mut my_chip8 = Chip8::initialize();
+        my_chip8.load_fontset();
+        for (n, opcode) in rom.iter().enumerate() {
+            my_chip8.memory[0x200 + n * 2..0x200 + n * 2 + 2].copy_from_slice(&opcode.to_be_bytes());
+        }
+        my_chip8.quirks = Preset::ALL[preset].quirks();
+        my_chip8.seed(seed);
+        let mut frame = vec![0; (WIDTH * HEIGHT * 4) as usize];
+
+        // a new set of held keys every 60Hz frame's worth of cycles
+        for pressed in keys.iter() {
+            for (key, held) in my_chip8.key.iter_mut().enumerate() {
+                *held = pressed & (1 << key) != 0;
+            }
+            for _ in 0..8 {
+                // unknown opcodes don't advance pc, so step over them to
+                // keep the rest of the ROM running
+                if let Err(Chip8Error::UnknownOpcode(opcode)) = my_chip8.step() {
+                    prop_assert_eq!(opcode, my_chip8.opcode);
+                    prop_assert_eq!(DecodedInstruction::new(opcode).class, "????");
+                    my_chip8.pc += 2;
+                }
+            }
+            my_chip8.tick_timers();
+            my_chip8.draw(&mut frame);
+        }
+    }
+}
@@ -1,4 +1,49 @@
 use crate::Chip8;
+use crate::audio::{Tone, DEFAULT_PITCH};
+use crate::events::Event;
+use crate::srcmap::SourceMap;
+use crate::watch::Watch;
+use crate::processor::{byte_to_bcd, nibble_to_font_addr, Chip8Error, LoadError, BIG_FONT_ADDR, BIG_FONTSET, TIMER_PERIOD, TRAINER_ADDR};
+use crate::{WIDTH, HEIGHT};
+use crate::quirks::Preset;
+
+// compare the display against ASCII art of its top-left corner, '#' for lit
+// pixels and '.' for unlit ones (whitespace is ignored); every pixel outside
+// the art must be unlit
+macro_rules! assert_gfx {
+    ($chip8:expr, $art:expr) => {
+        if let Some(diff) = gfx_diff($art, &$chip8.gfx_to_string()) {
+            panic!("display does not match:\n{}", diff);
+        }
+    };
+}
+
+fn gfx_diff(art: &str, actual: &str) -> Option<String> {
+    let mut expected = vec![vec!['.'; WIDTH as usize]; HEIGHT as usize];
+    let rows = art.lines()
+        .map(|line| line.chars().filter(|c| !c.is_whitespace()).collect::<Vec<char>>())
+        .filter(|row| !row.is_empty());
+    for (y, row) in rows.enumerate() {
+        for (x, pixel) in row.into_iter().enumerate() {
+            expected[y][x] = pixel;
+        }
+    }
+
+    let mut diff = String::new();
+    for (y, actual_row) in actual.lines().enumerate() {
+        let expected_row: String = expected[y].iter().collect();
+        if expected_row != actual_row {
+            let markers: String = expected_row.chars().zip(actual_row.chars())
+                .map(|(e, a)| if e == a { ' ' } else { '^' })
+                .collect();
+            diff.push_str(&format!("row {:2} expected {}\n", y, expected_row));
+            diff.push_str(&format!("       actual   {}\n", actual_row));
+            diff.push_str(&format!("                {}\n", markers));
+        }
+    }
+
+    if diff.is_empty() { None } else { Some(diff) }
+}
 
 #[test]
 fn test_initialize() {
@@ -8,6 +53,17 @@ fn test_initialize() {
     assert_eq!(my_chip8.stack, [0; 16]);
 }
 
+#[test]
+fn test_verify_fontset() {
+    let mut my_chip8 = Chip8::initialize();
+    assert!(!my_chip8.verify_fontset());
+    my_chip8.load_fontset();
+    assert!(my_chip8.verify_fontset());
+
+    my_chip8.memory[0x12] ^= 0xff;
+    assert!(!my_chip8.verify_fontset());
+}
+
 #[test]
 fn test_fontset() {
     let mut my_chip8 = Chip8::initialize();
@@ -37,4 +93,1317 @@ fn test_fontset() {
     }
 }
 
+#[test]
+fn test_self_jump_halts() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x200] = 0x12;
+    my_chip8.memory[0x201] = 0x00;
+
+    my_chip8.emulate_cycle();
+    assert!(my_chip8.halted);
+    assert_eq!(my_chip8.pc, 0x200);
+}
+
+#[test]
+fn test_jump_elsewhere_does_not_halt() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x200] = 0x12;
+    my_chip8.memory[0x201] = 0x04;
+
+    my_chip8.emulate_cycle();
+    assert!(!my_chip8.halted);
+    assert_eq!(my_chip8.pc, 0x204);
+}
+
+#[test]
+fn test_skip_over_long_load() {
+    let mut my_chip8 = Chip8::initialize();
+    // SE V0, 0x00 followed by the four byte F000 1234
+    my_chip8.memory[0x200..0x206].copy_from_slice(&[0x30, 0x00, 0xf0, 0x00, 0x12, 0x34]);
+
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x206);
+}
+
+#[test]
+fn test_long_load() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x200..0x204].copy_from_slice(&[0xF0, 0x00, 0x12, 0x34]);
+
+    assert_eq!(my_chip8.step(), Ok(()));
+    assert_eq!((my_chip8.i, my_chip8.pc), (0x1234, 0x204));
+
+    // the address may not fit before the end of memory
+    my_chip8.pc = 0xFFE;
+    my_chip8.memory[0xFFE..].copy_from_slice(&[0xF0, 0x00]);
+    my_chip8.memory[0x000..0x002].copy_from_slice(&[0x0A, 0xBC]);
+    assert_eq!(my_chip8.step(), Err(Chip8Error::MemoryOutOfBounds(0x1000)));
+    assert_eq!(my_chip8.i, 0x0ABC);
+}
+
+#[test]
+fn test_skip_over_regular_instruction() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x200..0x204].copy_from_slice(&[0x30, 0x00, 0x60, 0x01]);
+
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x204);
+}
+
+#[test]
+fn test_nibble_to_font_addr() {
+    for nibble in 0..16u8 {
+        assert_eq!(nibble_to_font_addr(nibble), nibble as u16 * 5);
+        // only the low nibble selects the glyph
+        assert_eq!(nibble_to_font_addr(nibble | 0xf0), nibble as u16 * 5);
+    }
+}
+
+#[test]
+fn test_byte_to_bcd() {
+    for b in 0..=255u8 {
+        let [hundreds, tens, ones] = byte_to_bcd(b);
+        assert!(hundreds <= 2 && tens <= 9 && ones <= 9);
+        assert_eq!(hundreds as u16 * 100 + tens as u16 * 10 + ones as u16, b as u16);
+    }
+}
+
+#[test]
+fn test_draw_font_glyph() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    // LD F, V0 then DRW V0, V0, 5 with V0 = 0
+    my_chip8.memory[0x200..0x204].copy_from_slice(&[0xf0, 0x29, 0xd0, 0x05]);
+
+    my_chip8.emulate_cycle();
+    my_chip8.emulate_cycle();
+    assert_gfx!(my_chip8, r#"
+        # # # #
+        # . . #
+        # . . #
+        # . . #
+        # # # #
+    "#);
+}
+
+#[test]
+#[should_panic(expected = "row  1 expected #..#")]
+fn test_assert_gfx_reports_difference() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.set_pixel(0, 0, 1);
+    my_chip8.set_pixel(1, 1, 1);
+    assert_gfx!(my_chip8, r#"
+        # . . .
+        # . . #
+    "#);
+}
+
+#[test]
+fn test_exit_stops_execution() {
+    let mut my_chip8 = Chip8::initialize();
+    // EXIT followed by LD V0, 0x01
+    my_chip8.memory[0x200..0x204].copy_from_slice(&[0x00, 0xfd, 0x60, 0x01]);
+
+    my_chip8.emulate_cycle();
+    assert!(my_chip8.exited);
+
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x200);
+    assert_eq!(my_chip8.v[0], 0);
+}
+
+#[test]
+fn test_skip_if_key_pressed() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.v[1] = 0xa;
+
+    my_chip8.op_ex9e(1);
+    assert_eq!(my_chip8.pc, 0x202);
+
+    my_chip8.pc = 0x200;
+    my_chip8.key[0xa] = true;
+    my_chip8.op_ex9e(1);
+    assert_eq!(my_chip8.pc, 0x204);
+}
+
+#[test]
+fn test_skip_if_key_not_pressed() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.v[1] = 0xa;
+
+    my_chip8.op_exa1(1);
+    assert_eq!(my_chip8.pc, 0x204);
+
+    my_chip8.pc = 0x200;
+    my_chip8.key[0xa] = true;
+    my_chip8.op_exa1(1);
+    assert_eq!(my_chip8.pc, 0x202);
+}
+
+#[test]
+fn test_run_for_budget() {
+    let mut my_chip8 = Chip8::initialize();
+    // ADD V0, 0x01 then JP 0x200
+    my_chip8.memory[0x200..0x204].copy_from_slice(&[0x70, 0x01, 0x12, 0x00]);
+
+    let count = my_chip8.run_for(my_chip8.cycle_time() * 10);
+    assert_eq!(count, 10);
+    assert_eq!(my_chip8.v[0], 5);
+
+    // a budget smaller than one instruction runs nothing
+    assert_eq!(my_chip8.run_for(my_chip8.cycle_time() / 2), 0);
+}
+
+#[test]
+fn test_decode() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x200] = 0xd1;
+    my_chip8.memory[0x201] = 0x23;
+
+    let decoded = my_chip8.decode();
+    assert_eq!(decoded.opcode, 0xd123);
+    assert_eq!(decoded.nibbles, (0xd, 0x1, 0x2, 0x3));
+    assert_eq!((decoded.x, decoded.y, decoded.n), (1, 2, 3));
+    assert_eq!(decoded.kk, 0x23);
+    assert_eq!(decoded.nnn, 0x123);
+    assert_eq!(decoded.mnemonic, "DRW");
+}
+
+#[test]
+fn test_draw_memory() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x123] = 0x80;
+
+    let mut frame = vec![0; 4096 * 4];
+    my_chip8.draw_memory(&mut frame);
+    assert_eq!(&frame[0x123 * 4..0x124 * 4], &[0x80, 0x80, 0x80, 0xff]);
+    assert_eq!(&frame[0x124 * 4..0x125 * 4], &[0x00, 0x00, 0x00, 0xff]);
+}
+
+#[test]
+fn test_cycle_driven_timers() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.cycle_timers = true;
+    my_chip8.delay_timer = 10;
+    my_chip8.sound_timer = 10;
+    // JP 0x200 forever
+    my_chip8.memory[0x200..0x202].copy_from_slice(&[0x12, 0x00]);
+
+    // instructions needed to cover one 60Hz period of emulated time
+    let cycle_time = my_chip8.cycle_time().as_nanos();
+    let cycles_per_tick = (TIMER_PERIOD.as_nanos() + cycle_time - 1) / cycle_time;
+    for _ in 0..cycles_per_tick - 1 {
+        my_chip8.emulate_cycle();
+    }
+    assert_eq!(my_chip8.delay_timer, 10);
+
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.delay_timer, 9);
+    assert_eq!(my_chip8.sound_timer, 9);
+}
+
+#[test]
+fn test_clock_speed_sets_cycle_time() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.clock_speed = 1000;
+    assert_eq!(my_chip8.cycle_time(), std::time::Duration::from_millis(1));
+}
 
+#[test]
+fn test_key_wait_loop_is_idle() {
+    let mut my_chip8 = Chip8::initialize();
+    // SKP V0; JP 0x200
+    my_chip8.memory[0x200..0x204].copy_from_slice(&[0xe0, 0x9e, 0x12, 0x00]);
+    my_chip8.v[0] = 0x5;
+    assert!(my_chip8.is_idle());
+
+    // still idle while sitting on the jump back
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x202);
+    assert!(my_chip8.is_idle());
+
+    my_chip8.pc = 0x200;
+    my_chip8.key[0x5] = true;
+    assert!(!my_chip8.is_idle());
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x204);
+}
+
+#[test]
+fn test_key_release_loop_is_idle() {
+    let mut my_chip8 = Chip8::initialize();
+    // SKNP V0; JP 0x200
+    my_chip8.memory[0x200..0x204].copy_from_slice(&[0xe0, 0xa1, 0x12, 0x00]);
+    assert!(!my_chip8.is_idle());
+
+    my_chip8.key[0x0] = true;
+    assert!(my_chip8.is_idle());
+}
+
+#[test]
+fn test_busy_loop_is_not_idle() {
+    let mut my_chip8 = Chip8::initialize();
+    // ADD V0, 0x01; JP 0x200
+    my_chip8.memory[0x200..0x204].copy_from_slice(&[0x70, 0x01, 0x12, 0x00]);
+    assert!(!my_chip8.is_idle());
+}
+
+#[test]
+fn test_canonical_dump_format() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.seed(0);
+    // RND V0, 0x0F; LD F, V0; DRW V1, V1, 5; JP 0x206
+    my_chip8.memory[0x200..0x208].copy_from_slice(&[0xc0, 0x0f, 0xf0, 0x29, 0xd1, 0x15, 0x12, 0x06]);
+    for _ in 0..4 {
+        my_chip8.emulate_cycle();
+    }
+
+    assert_eq!(
+        my_chip8.canonical_dump(),
+        "PC 0206\nI 004B\nSP 00\nV 0F 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00\nGFX 6C995D229CF5ED24\n"
+    );
+}
+
+#[test]
+fn test_rpl_flags() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.v[..9].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8, 9]);
+    // LD R, VF stores at most the 8 RPL flags
+    my_chip8.op_fx75(0xf);
+    assert_eq!(my_chip8.rpl, [1, 2, 3, 4, 5, 6, 7, 8]);
+
+    my_chip8.v = [0; 16];
+    my_chip8.op_fx85(2);
+    assert_eq!(&my_chip8.v[..4], &[1, 2, 3, 0]);
+}
+
+#[test]
+fn test_to_svg_single_pixel() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.set_pixel(0, 0, 1);
+
+    let svg = my_chip8.to_svg(10);
+    assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"640\" height=\"320\""));
+    assert_eq!(svg.matches("<rect").count(), 1);
+    assert!(svg.contains("<rect x=\"0\" y=\"0\" width=\"10\" height=\"10\" fill=\"#ffffff\"/>"));
+}
+
+#[test]
+fn test_dxyn_vf_as_coordinate() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    // '0' glyph at x = VF = 8 over a pixel that is already lit
+    my_chip8.i = nibble_to_font_addr(0);
+    my_chip8.v[0xf] = 8;
+    my_chip8.set_pixel(8, 0, 1);
+    my_chip8.op_dxyn(0xf, 0x0, 5);
+
+    // drawn at the original VF, not at 0
+    assert_eq!(my_chip8.pixel(0, 0), 0);
+    assert_eq!(my_chip8.pixel(9, 0), 1);
+    assert_eq!(my_chip8.pixel(8, 0), 0);
+    // then VF reports the collision
+    assert_eq!(my_chip8.v[0xf], 1);
+}
+
+#[test]
+fn test_dxyn_wraps_at_62_30() {
+    // a 4x3 block at (62, 30) wraps onto all four corners; the frame puts
+    // pixel (x, y) at (y * WIDTH + x) * 4, as before gfx became row-major
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x300..0x303].copy_from_slice(&[0xF0; 3]);
+    my_chip8.i = 0x300;
+    my_chip8.v[0x0] = 62;
+    my_chip8.v[0x1] = 30;
+    my_chip8.op_dxyn(0x0, 0x1, 3);
+
+    let mut frame = vec![0; (WIDTH * HEIGHT * 4) as usize];
+    my_chip8.draw(&mut frame);
+    let lit: Vec<(usize, usize)> = frame.chunks_exact(4)
+        .enumerate()
+        .filter(|(_, pixel)| pixel[0] == 0xff)
+        .map(|(i, _)| (i % WIDTH as usize, i / WIDTH as usize))
+        .collect();
+    assert_eq!(lit, [
+        (0, 0), (1, 0), (62, 0), (63, 0),
+        (0, 30), (1, 30), (62, 30), (63, 30),
+        (0, 31), (1, 31), (62, 31), (63, 31),
+    ]);
+    assert_eq!(my_chip8.pixel(1, 0), 1);
+    assert_eq!(my_chip8.gfx[0][1], 1);
+}
+
+#[test]
+fn test_override_opcode() {
+    let mut my_chip8 = Chip8::initialize();
+    // Fx FF: custom "set Vx to 0x42"
+    my_chip8.override_opcode(0xf0ff, 0xf0ff, |chip8, opcode| {
+        chip8.v[((opcode & 0x0F00) >> 8) as usize] = 0x42;
+        chip8.pc += 2;
+    });
+    my_chip8.memory[0x200..0x202].copy_from_slice(&[0xf3, 0xff]);
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.v[3], 0x42);
+    assert_eq!(my_chip8.pc, 0x202);
+}
+
+#[test]
+fn test_max_stack_depth() {
+    let mut my_chip8 = Chip8::initialize();
+    // 0x200: CALL 0x204, 0x204: CALL 0x208, 0x208: RET
+    my_chip8.memory[0x200..0x20a].copy_from_slice(&[0x22, 0x04, 0x00, 0xee, 0x22, 0x08, 0x00, 0xee, 0x00, 0xee]);
+    my_chip8.emulate_cycle();
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.sp, 2);
+
+    // returning does not lower the high-water mark
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.sp, 1);
+    assert_eq!(my_chip8.max_stack_depth(), 2);
+}
+
+#[test]
+fn test_to_text() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.set_pixel(2, 1, 1);
+
+    let text = my_chip8.to_text('1', '0');
+    let rows: Vec<&str> = text.lines().collect();
+    assert_eq!(rows.len(), HEIGHT as usize);
+    assert_eq!(&rows[1][..4], "0010");
+    assert_eq!(text.matches('1').count(), 1);
+
+    let text = my_chip8.to_text_with('1', '0', ",", ";");
+    assert!(text.starts_with("0,0,0"));
+    assert!(text.contains(";0,0,1,0"));
+}
+
+#[test]
+fn test_no_draw_warning() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.cycle_timers = true;
+    my_chip8.draw_timeout = 3;
+    // JP 0x200
+    my_chip8.memory[0x200..0x202].copy_from_slice(&[0x12, 0x00]);
+
+    // one timer tick per instruction
+    my_chip8.clock_speed = 60;
+
+    my_chip8.emulate_cycle();
+    my_chip8.emulate_cycle();
+    assert!(!my_chip8.no_draw_warning());
+    my_chip8.emulate_cycle();
+    assert!(my_chip8.no_draw_warning());
+
+    // drawing resets the count
+    my_chip8.op_00e0();
+    assert!(!my_chip8.no_draw_warning());
+}
+
+#[test]
+fn test_transparent_background() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.palette.background = [0x00, 0x00, 0x00, 0x40];
+    my_chip8.set_pixel(0, 0, 1);
+
+    let mut frame = vec![0; (WIDTH * HEIGHT * 4) as usize];
+    my_chip8.draw(&mut frame);
+    assert_eq!(&frame[0..4], &[0xff, 0xff, 0xff, 0xff]);
+    assert_eq!(&frame[4..8], &[0x00, 0x00, 0x00, 0x40]);
+}
+
+#[test]
+fn test_set_colors() {
+    let mut my_chip8 = Chip8::initialize();
+    let (amber, brown) = ([0xff, 0xb0, 0x00, 0xff], [0x20, 0x10, 0x00, 0xff]);
+    my_chip8.set_colors(amber, brown);
+    my_chip8.set_pixel(1, 0, 1);
+
+    let mut frame = vec![0; (WIDTH * HEIGHT * 4) as usize];
+    my_chip8.draw(&mut frame);
+    assert_eq!(&frame[0..4], &brown);
+    assert_eq!(&frame[4..8], &amber);
+}
+
+#[test]
+fn test_warn_sprite_write() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.warn_sprite_write = true;
+    my_chip8.i = nibble_to_font_addr(0);
+    my_chip8.op_dxyn(0, 0, 5);
+
+    // BCD into the bytes just drawn from
+    my_chip8.i += 2;
+    my_chip8.op_fx33(0);
+    assert!(my_chip8.last_warning.is_some());
+
+    // writes past the sprite are fine
+    my_chip8.last_warning = None;
+    my_chip8.i = nibble_to_font_addr(0) + 5;
+    my_chip8.op_fx55(0);
+    assert!(my_chip8.last_warning.is_none());
+}
+
+#[test]
+fn test_trace_every() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.trace_every = 10;
+    // JP 0x200
+    my_chip8.memory[0x200..0x202].copy_from_slice(&[0x12, 0x00]);
+    for _ in 0..100 {
+        my_chip8.emulate_cycle();
+    }
+    assert_eq!(my_chip8.trace_lines, 10);
+}
+
+#[test]
+fn test_load_vip_rom() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    let image: Vec<u8> = (0..0x200).map(|i| (i * 7) as u8).collect();
+    my_chip8.load_vip_rom(&image).unwrap();
+
+    // LD V1, [I] reads from the interpreter region
+    my_chip8.i = 0x1f0;
+    my_chip8.op_fx65(1);
+    assert_eq!(&my_chip8.v[..2], &image[0x1f0..0x1f2]);
+
+    assert!(my_chip8.load_vip_rom(&[0; 0x201]).is_err());
+}
+
+#[test]
+fn test_collision_event() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    let events = my_chip8.subscribe();
+    my_chip8.i = nibble_to_font_addr(0);
+    my_chip8.v[0] = 3;
+
+    my_chip8.op_dxyn(0, 1, 5);
+    assert_eq!(events.try_iter().collect::<Vec<_>>(), vec![Event::Draw]);
+
+    // drawing the same sprite again erases it
+    my_chip8.op_dxyn(0, 1, 5);
+    assert_eq!(
+        events.try_iter().collect::<Vec<_>>(),
+        vec![Event::Draw, Event::Collision { x: 3, y: 0 }]
+    );
+}
+
+#[test]
+fn test_trainer_runs_each_frame() {
+    let mut my_chip8 = Chip8::initialize();
+    // LD I, 0x300; LD V0, [I]; ADD V0, 1; LD [I], V0; RET
+    my_chip8.load_trainer(&[0xa3, 0x00, 0xf0, 0x65, 0x70, 0x01, 0xf0, 0x55, 0x00, 0xee]).unwrap();
+    my_chip8.v[0] = 0x42;
+    my_chip8.i = 0x123;
+
+    for _ in 0..3 {
+        my_chip8.tick_timers();
+    }
+    assert_eq!(my_chip8.memory[0x300], 3);
+
+    // the ROM's own state is untouched
+    assert_eq!(my_chip8.v[0], 0x42);
+    assert_eq!(my_chip8.i, 0x123);
+    assert_eq!(my_chip8.pc, 0x200);
+    assert_eq!(my_chip8.memory[TRAINER_ADDR as usize], 0xa3);
+}
+
+#[test]
+fn test_ret_returns_after_call() {
+    let mut my_chip8 = Chip8::initialize();
+    // 0x200: CALL 0x300, 0x300: RET
+    my_chip8.memory[0x200..0x202].copy_from_slice(&[0x23, 0x00]);
+    my_chip8.memory[0x300..0x302].copy_from_slice(&[0x00, 0xee]);
+
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x300);
+    assert_eq!(my_chip8.stack[0], 0x202);
+
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.pc, 0x202);
+    assert_eq!(my_chip8.sp, 0);
+}
+
+#[test]
+fn test_nested_ret_resumes_after_each_call() {
+    let mut my_chip8 = Chip8::initialize();
+    // 0x200: CALL 0x300, 0x202: LD V0, 1
+    // 0x300: CALL 0x400, 0x302: LD V1, 2, 0x304: RET
+    // 0x400: RET
+    my_chip8.memory[0x200..0x204].copy_from_slice(&[0x23, 0x00, 0x60, 0x01]);
+    my_chip8.memory[0x300..0x306].copy_from_slice(&[0x24, 0x00, 0x61, 0x02, 0x00, 0xee]);
+    my_chip8.memory[0x400..0x402].copy_from_slice(&[0x00, 0xee]);
+
+    let mut pcs = Vec::new();
+    for _ in 0..6 {
+        my_chip8.emulate_cycle();
+        pcs.push(my_chip8.pc);
+    }
+    assert_eq!(pcs, [0x300, 0x400, 0x302, 0x304, 0x202, 0x204]);
+    assert_eq!((my_chip8.v[0x0], my_chip8.v[0x1]), (1, 2));
+    assert_eq!(my_chip8.sp, 0);
+}
+
+#[test]
+fn test_ret_with_empty_stack() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x200..0x202].copy_from_slice(&[0x00, 0xee]);
+
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.error, Some(Chip8Error::StackUnderflow));
+    assert_eq!((my_chip8.pc, my_chip8.sp), (0x200, 0));
+
+    // a faulted machine stays put
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.instructions, 1);
+}
+
+#[test]
+fn test_call_past_full_stack() {
+    let mut my_chip8 = Chip8::initialize();
+    // 0x200: CALL 0x200, forever
+    my_chip8.memory[0x200..0x202].copy_from_slice(&[0x22, 0x00]);
+
+    for _ in 0..16 {
+        my_chip8.emulate_cycle();
+    }
+    assert_eq!(my_chip8.error, None);
+    assert_eq!(my_chip8.sp, 16);
+
+    // the 17th frame doesn't fit
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.error, Some(Chip8Error::StackOverflow));
+    assert_eq!((my_chip8.pc, my_chip8.sp), (0x200, 16));
+    assert_eq!(my_chip8.stack, [0x202; 16]);
+}
+
+#[test]
+fn test_step_reports_unknown_opcode() {
+    let mut my_chip8 = Chip8::initialize();
+    // 0x200: LD V0, 1, 0x202: unknown
+    my_chip8.memory[0x200..0x204].copy_from_slice(&[0x60, 0x01, 0xff, 0xff]);
+
+    assert_eq!(my_chip8.step(), Ok(()));
+    assert_eq!(my_chip8.step(), Err(Chip8Error::UnknownOpcode(0xffff)));
+    // the caller decides what to do; the machine can still run
+    assert_eq!(my_chip8.error, None);
+    assert_eq!(my_chip8.pc, 0x202);
+}
+
+#[test]
+fn test_step_reports_faults() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x200..0x202].copy_from_slice(&[0x00, 0xee]);
+    assert_eq!(my_chip8.step(), Err(Chip8Error::StackUnderflow));
+    // and keeps reporting them, without running anything
+    assert_eq!(my_chip8.step(), Err(Chip8Error::StackUnderflow));
+    assert_eq!(my_chip8.instructions, 1);
+
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x200..0x202].copy_from_slice(&[0x22, 0x00]);
+    my_chip8.sp = 16;
+    assert_eq!(my_chip8.step(), Err(Chip8Error::StackOverflow));
+}
+
+#[test]
+fn test_step_reports_memory_out_of_bounds() {
+    let mut my_chip8 = Chip8::initialize();
+    // 0x200: LD [I], V3 with I = 0xFFE
+    my_chip8.memory[0x200..0x202].copy_from_slice(&[0xf3, 0x55]);
+    my_chip8.i = 0xffe;
+    my_chip8.v[..4].copy_from_slice(&[1, 2, 3, 4]);
+
+    assert_eq!(my_chip8.step(), Err(Chip8Error::MemoryOutOfBounds(0x1000)));
+    // the access still wraps, as it always has
+    assert_eq!(my_chip8.memory[0xffe..], [1, 2]);
+    assert_eq!(my_chip8.memory[..2], [3, 4]);
+    assert_eq!((my_chip8.pc, my_chip8.error), (0x202, None));
+}
+
+#[test]
+fn test_fx0a_waits_for_key() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x200..0x202].copy_from_slice(&[0xf3, 0x0a]);
+
+    for _ in 0..3 {
+        my_chip8.emulate_cycle();
+        assert_eq!(my_chip8.pc, 0x200);
+    }
+
+    my_chip8.key[0xA] = true;
+    my_chip8.emulate_cycle();
+    assert_eq!((my_chip8.v[0x3], my_chip8.pc), (0xA, 0x202));
+}
+
+#[test]
+fn test_fx0a_sees_key_f() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.key[0xF] = true;
+    my_chip8.op_fx0a(0x3);
+    assert_eq!((my_chip8.v[0x3], my_chip8.pc), (0xF, 0x202));
+}
+
+#[test]
+fn test_register_watchpoint() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.add_watchpoint(Watch::parse("VA==0").unwrap());
+    // LD VA, 0x03; ADD VA, 0xFF (x3); JP 0x208
+    my_chip8.memory[0x200..0x20a].copy_from_slice(&[0x6a, 0x03, 0x7a, 0xff, 0x7a, 0xff, 0x7a, 0xff, 0x12, 0x08]);
+
+    for _ in 0..10 {
+        my_chip8.emulate_cycle();
+    }
+    assert!(my_chip8.paused);
+    assert_eq!(my_chip8.watch_hit, Some(Watch::Register { reg: 0xa, value: 0 }));
+    assert_eq!(my_chip8.v[0xa], 0);
+    assert_eq!(my_chip8.pc, 0x208);
+
+    my_chip8.resume();
+    assert_eq!(my_chip8.watch_hit, None);
+}
+
+#[test]
+fn test_parse_watchpoint() {
+    assert_eq!(Watch::parse("VA==0"), Ok(Watch::Register { reg: 0xa, value: 0 }));
+    assert_eq!(Watch::parse("0x300==0x10"), Ok(Watch::Memory { addr: 0x300, value: 0x10 }));
+    assert!(Watch::parse("VG==0").is_err());
+    assert!(Watch::parse("V1=256").is_err());
+}
+
+#[test]
+fn test_load_program_skips_header() {
+    let path = std::env::temp_dir().join(format!("chip8-header-{}.ch8", std::process::id()));
+    std::fs::write(&path, [0xde, 0xad, 0xbe, 0xef, 0x60, 0x01, 0x12, 0x02, 0xff]).unwrap();
+
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.skip_header = 4;
+    my_chip8.skip_trailer = 1;
+    my_chip8.load_program(path.to_str().unwrap()).unwrap();
+    assert_eq!(&my_chip8.memory[0x200..0x205], &[0x60, 0x01, 0x12, 0x02, 0x00]);
+
+    my_chip8.skip_header = 9;
+    assert!(my_chip8.load_program(path.to_str().unwrap()).is_err());
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_location_uses_source_map() {
+    let mut my_chip8 = Chip8::initialize();
+    assert_eq!(my_chip8.location(0x202), "0x202");
+
+    my_chip8.source_map = Some(SourceMap::parse("0x200 : main\n0x202 v0 := 5\n").unwrap());
+    assert_eq!(my_chip8.location(0x202), "0x202 (v0 := 5)");
+    assert_eq!(my_chip8.location(0x204), "0x204");
+
+    assert!(SourceMap::parse("main 0x200").is_err());
+}
+
+#[test]
+fn test_misaligned_pc() {
+    // 1201: jump to an odd address
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x200] = 0x12;
+    my_chip8.memory[0x201] = 0x01;
+    my_chip8.emulate_cycle();
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.error, None);
+    assert_eq!(my_chip8.last_warning, None);
+
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x200] = 0x12;
+    my_chip8.memory[0x201] = 0x01;
+    my_chip8.warn_misalign = true;
+    my_chip8.emulate_cycle();
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.error, None);
+    assert_eq!(my_chip8.last_warning.as_deref(), Some("instruction fetch from odd address 0x201"));
+
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0x200] = 0x12;
+    my_chip8.memory[0x201] = 0x01;
+    my_chip8.strict_align = true;
+    my_chip8.emulate_cycle();
+    my_chip8.emulate_cycle();
+    assert_eq!(my_chip8.error, Some(Chip8Error::MisalignedPc(0x201)));
+    assert_eq!(my_chip8.pc, 0x201);
+}
+
+// the display as a greyscale PNG, each pixel `scale` pixels square
+fn write_png(my_chip8: &Chip8, path: &std::path::Path, scale: usize) {
+    let (width, height) = (WIDTH as usize * scale, HEIGHT as usize * scale);
+    let mut data = vec![0u8; width * height];
+    for (offset, pixel) in data.iter_mut().enumerate() {
+        let (x, y) = (offset % width / scale, offset / width / scale);
+        *pixel = if my_chip8.pixel(x, y) != 0 { 0xff } else { 0x00 };
+    }
+    let mut encoder = png::Encoder::new(std::fs::File::create(path).unwrap(), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Grayscale);
+    encoder.write_header().unwrap().write_image_data(&data).unwrap();
+}
+
+#[test]
+fn test_assert_matches_image() {
+    let path = std::env::temp_dir().join(format!("chip8-frame-{}.png", std::process::id()));
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.i = nibble_to_font_addr(0xA);
+    my_chip8.op_dxyn(0x0, 0x1, 5);
+
+    // a frame matches its own screenshot, at any whole scale
+    write_png(&my_chip8, &path, 10);
+    assert_eq!(my_chip8.assert_matches_image(path.to_str().unwrap()), Ok(()));
+
+    // the screenshot with two pixels flipped
+    my_chip8.set_pixel(0, 0, my_chip8.pixel(0, 0) ^ 1);
+    my_chip8.set_pixel(63, 31, my_chip8.pixel(63, 31) ^ 1);
+    write_png(&my_chip8, &path, 1);
+    my_chip8.set_pixel(0, 0, my_chip8.pixel(0, 0) ^ 1);
+    my_chip8.set_pixel(63, 31, my_chip8.pixel(63, 31) ^ 1);
+    assert_eq!(my_chip8.assert_matches_image(path.to_str().unwrap()), Err(vec![(0, 0), (63, 31)]));
+
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_instructions_per_draw() {
+    // D005, then 18 x 7001, then 1200: a draw every 20 instructions
+    let mut my_chip8 = Chip8::initialize();
+    assert_eq!(my_chip8.instructions_per_draw(), None);
+    my_chip8.memory[0x200] = 0xD0;
+    my_chip8.memory[0x201] = 0x05;
+    for n in 0..18 {
+        my_chip8.memory[0x202 + n * 2] = 0x70;
+        my_chip8.memory[0x203 + n * 2] = 0x01;
+    }
+    my_chip8.memory[0x226] = 0x12;
+    my_chip8.memory[0x227] = 0x00;
+
+    for _ in 0..2000 {
+        my_chip8.emulate_cycle();
+    }
+    let average = my_chip8.instructions_per_draw().unwrap();
+    assert!((average - 20.0).abs() < 0.5, "average {}", average);
+}
+
+#[test]
+fn test_load_memory_image() {
+    let mut image = [0u8; 4096];
+    image[0x000] = 0xAB;
+    image[0x1FF] = 0xCD;
+    image[0x200] = 0x12;
+    image[0xFFF] = 0xEF;
+
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_memory_image(&image).unwrap();
+    assert_eq!(my_chip8.memory[0x000], 0xAB);
+    assert_eq!(my_chip8.memory[0x1FF], 0xCD);
+    assert_eq!(my_chip8.memory[0x200], 0x12);
+    assert_eq!(my_chip8.memory[0xFFF], 0xEF);
+    assert_eq!(my_chip8.memory[0x100], 0x00);
+
+    assert!(my_chip8.load_memory_image(&image[..4095]).is_err());
+}
+
+#[test]
+fn test_fx55_fx65_round_trip_include_vx() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.i = 0x300;
+    my_chip8.v[..6].copy_from_slice(&[1, 2, 3, 4, 5, 6]);
+    my_chip8.v[6] = 7;
+    my_chip8.op_fx55(5);
+    assert_eq!(my_chip8.memory[0x300..0x307], [1, 2, 3, 4, 5, 6, 0]);
+
+    my_chip8.v = [0; 16];
+    my_chip8.op_fx65(5);
+    assert_eq!(my_chip8.v[..7], [1, 2, 3, 4, 5, 6, 0]);
+}
+
+#[test]
+fn test_9xy0_compares_whole_registers() {
+    // Vx = 0x02, Vy = 0x20 differ, though Vy >> 4 would equal Vx
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.v[0x1] = 0x02;
+    my_chip8.v[0x2] = 0x20;
+    my_chip8.op_9xy0(0x1, 0x2);
+    assert_eq!(my_chip8.pc, 0x204);
+
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.v[0x1] = 0x20;
+    my_chip8.v[0x2] = 0x02;
+    my_chip8.op_9xy0(0x1, 0x2);
+    assert_eq!(my_chip8.pc, 0x204);
+
+    // equal registers don't skip
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.v[0x1] = 0x20;
+    my_chip8.v[0x2] = 0x20;
+    my_chip8.op_9xy0(0x1, 0x2);
+    assert_eq!(my_chip8.pc, 0x202);
+}
+
+#[test]
+fn test_fx33_stores_bcd() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.i = 0x300;
+    my_chip8.v[0x4] = 254;
+    my_chip8.op_fx33(0x4);
+    assert_eq!(my_chip8.memory[0x300..0x303], [2, 5, 4]);
+}
+
+#[test]
+fn test_clear_display() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.op_dxyn(0x0, 0x0, 5);
+    my_chip8.draw_flag = false;
+
+    my_chip8.clear_display();
+    assert!(my_chip8.gfx.iter().all(|column| column.iter().all(|&pixel| pixel == 0)));
+    assert!(my_chip8.draw_flag);
+    assert_eq!(my_chip8.pc, 0x202);
+
+    // with pixels lit on both planes, 00E0 clears only the selected plane
+    // while clear_display clears both
+    my_chip8.set_pixel(0, 0, 3);
+    my_chip8.set_pixel(1, 0, 1);
+    my_chip8.set_pixel(2, 0, 2);
+    my_chip8.op_fn01(2);
+    my_chip8.op_00e0();
+    assert_eq!((my_chip8.pixel(0, 0), my_chip8.pixel(1, 0), my_chip8.pixel(2, 0)), (1, 1, 0));
+    my_chip8.set_pixel(2, 0, 2);
+    my_chip8.clear_display();
+    assert_eq!((my_chip8.pixel(0, 0), my_chip8.pixel(1, 0), my_chip8.pixel(2, 0)), (0, 0, 0));
+}
+
+#[test]
+fn test_8xy7_borrow_wraps() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.v[0x1] = 3;
+    my_chip8.v[0x2] = 1;
+    my_chip8.op_8xy7(0x1, 0x2);
+    assert_eq!(my_chip8.v[0x1], 254);
+    assert_eq!(my_chip8.v[0xf], 0);
+}
+
+#[test]
+fn test_fx30_large_font() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.load_large_font();
+    my_chip8.v[0x2] = 7;
+    my_chip8.op_fx30(0x2);
+    assert_eq!(my_chip8.i, BIG_FONT_ADDR + 70);
+    assert_eq!(my_chip8.memory[my_chip8.i as usize..my_chip8.i as usize + 10], BIG_FONTSET[70..80]);
+    assert_eq!(my_chip8.last_warning, None);
+    // the small font is untouched
+    assert!(my_chip8.verify_fontset());
+}
+
+#[test]
+fn test_fx30_falls_back_to_small_font() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.v[0x2] = 0x37;
+    my_chip8.op_fx30(0x2);
+    assert_eq!(my_chip8.i, nibble_to_font_addr(7));
+    assert_eq!(my_chip8.pc, 0x202);
+    // logged at warn level, and kept for callers to check
+    assert_eq!(my_chip8.last_warning.as_deref(), Some("0x200 Fx30 with no large glyph for 7, using the small font"));
+}
+
+#[test]
+fn test_load_program_too_large() {
+    let path = std::env::temp_dir().join(format!("chip8-large-{}.ch8", std::process::id()));
+    std::fs::write(&path, vec![0x12; 3585]).unwrap();
+
+    let mut my_chip8 = Chip8::initialize();
+    let err = my_chip8.load_program(path.to_str().unwrap()).unwrap_err();
+    assert_eq!(err.downcast_ref::<Chip8Error>(), Some(&Chip8Error::Load(LoadError::TooLarge { size: 3585, max: 3584 })));
+    assert_eq!(my_chip8.memory[0x200], 0x00);
+
+    // exactly filling memory is fine
+    std::fs::write(&path, vec![0x12; 3584]).unwrap();
+    my_chip8.load_program(path.to_str().unwrap()).unwrap();
+    assert_eq!(my_chip8.memory[0xFFF], 0x12);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_tone_follows_sound_timer() {
+    let mut my_chip8 = Chip8::initialize();
+    assert!(!my_chip8.tone());
+    my_chip8.sound_timer = 2;
+    assert!(my_chip8.tone());
+
+    // muted, the timer still runs but nothing should play
+    my_chip8.set_audio_enabled(false);
+    assert!(!my_chip8.tone());
+    my_chip8.tick_timers();
+    assert_eq!(my_chip8.sound_timer, 1);
+
+    my_chip8.set_audio_enabled(true);
+    my_chip8.tick_timers();
+    assert!(!my_chip8.tone());
+}
+
+#[test]
+fn test_rng_state_repeats_sequence() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.seed(7);
+    for _ in 0..5 {
+        my_chip8.op_cxkk(0x0, 0xFF);
+    }
+
+    // note the state partway through, then draw from it twice
+    let state = my_chip8.rng_state();
+    let draws = |chip8: &mut Chip8| -> Vec<u8> {
+        (0..16).map(|_| { chip8.op_cxkk(0x0, 0xFF); chip8.v[0x0] }).collect()
+    };
+    let first = draws(&mut my_chip8);
+
+    let mut other = Chip8::initialize();
+    other.set_rng_state(&state);
+    assert_eq!(other.rng_state(), state);
+    assert_eq!(draws(&mut other), first);
+
+    my_chip8.set_rng_state(&state);
+    assert_eq!(draws(&mut my_chip8), first);
+}
+
+#[test]
+fn test_load_program_bytes() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.skip_header = 2;
+    my_chip8.load_program_bytes(&[0xff, 0xff, 0x60, 0x2a, 0x12, 0x02]).unwrap();
+    assert_eq!(my_chip8.memory[0x200..0x204], [0x60, 0x2a, 0x12, 0x02]);
+    assert_ne!(my_chip8.rom_hash, 0);
+
+    assert_eq!(my_chip8.load_program_bytes(&[0x00]), Err(Chip8Error::Load(LoadError::SkipTooLong { skipped: 2, size: 1 })));
+}
+
+#[test]
+fn test_run_cycles_returns_pc() {
+    let mut my_chip8 = Chip8::initialize();
+    // LD V0, 1; ADD V0, 1; JP 0x202
+    my_chip8.load_program_bytes(&[0x60, 0x01, 0x70, 0x01, 0x12, 0x02]).unwrap();
+    assert_eq!(my_chip8.run_cycles(4), 0x204);
+    assert_eq!(my_chip8.v[0x0], 3);
+    assert_eq!(my_chip8.run_cycles(1), 0x202);
+    assert_eq!(my_chip8.instructions, 5);
+}
+
+#[test]
+fn test_hires_switch() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    // HIGH; LD V0, 120; LD V1, 60; DRW V0, V1, 5 (glyph 0 at I = 0); LOW
+    my_chip8.load_program_bytes(&[0x00, 0xFF, 0x60, 120, 0x61, 60, 0xD0, 0x15, 0x00, 0xFE]).unwrap();
+    my_chip8.set_pixel(0, 0, 1);
+
+    my_chip8.run_cycles(1);
+    assert_eq!(my_chip8.resolution(), (128, 64));
+    // switching clears the display
+    assert_eq!(my_chip8.pixel(0, 0), 0);
+
+    // past the low-res edges, wrapping at the hi-res ones
+    my_chip8.run_cycles(3);
+    assert_eq!(my_chip8.pixel(120, 60), 1);
+    assert_eq!(my_chip8.pixel(123, 63), 1);
+    assert_eq!(my_chip8.pixel(120, 0), 1);
+    assert_eq!(my_chip8.to_text('#', '.').lines().count(), 64);
+
+    let mut frame = vec![0; 128 * 64 * 4];
+    my_chip8.draw(&mut frame);
+    let pixel = (60 * 128 + 120) * 4;
+    assert_eq!(frame[pixel..pixel + 4], my_chip8.palette.foreground);
+
+    my_chip8.run_cycles(1);
+    assert_eq!(my_chip8.resolution(), (WIDTH, HEIGHT));
+    assert_eq!(my_chip8.pixel(120, 60), 0);
+
+    // SUPER-CHIP keeps the picture, scaled to the new resolution
+    let mut my_chip8 = Chip8::with_quirks(Preset::Schip.quirks());
+    // HIGH; LOW
+    my_chip8.load_program_bytes(&[0x00, 0xFF, 0x00, 0xFE]).unwrap();
+    my_chip8.set_pixel(1, 1, 1);
+
+    my_chip8.run_cycles(1);
+    for &(x, y) in [(2, 2), (3, 2), (2, 3), (3, 3)].iter() {
+        assert_eq!(my_chip8.pixel(x, y), 1, "({}, {})", x, y);
+    }
+    assert_eq!(my_chip8.pixel(1, 1), 0);
+
+    my_chip8.set_pixel(2, 2, 0);
+    my_chip8.set_pixel(3, 3, 0);
+    my_chip8.set_pixel(127, 63, 1);
+    my_chip8.run_cycles(1);
+    assert_eq!(my_chip8.pixel(1, 1), 1);
+    assert_eq!(my_chip8.pixel(63, 31), 1);
+    assert_eq!(my_chip8.to_text('#', '.').matches('#').count(), 2);
+}
+
+#[test]
+fn test_scroll_down() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.set_pixel(2, 0, 1);
+    my_chip8.set_pixel(5, 30, 1);
+    my_chip8.op_00cn(3);
+    assert_eq!(my_chip8.pc, 0x202);
+    assert!(my_chip8.draw_flag);
+    // moved down, and scrolled off the bottom rather than wrapping
+    assert_gfx!(my_chip8, "
+        ...
+        ...
+        ...
+        ..#
+    ");
+}
+
+#[test]
+fn test_scroll_right_and_left() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.set_pixel(0, 0, 1);
+    my_chip8.set_pixel(62, 1, 1);
+    my_chip8.op_00fb();
+    assert_gfx!(my_chip8, "
+        ....#
+    ");
+
+    my_chip8.set_pixel(1, 1, 1);
+    my_chip8.draw_flag = false;
+    my_chip8.op_00fc();
+    assert!(my_chip8.draw_flag);
+    assert_gfx!(my_chip8, "
+        #
+    ");
+}
+
+#[test]
+fn test_scroll_hires() {
+    // scrolling covers the whole 128x64 display in hi-res mode
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.hires = true;
+    my_chip8.set_pixel(100, 40, 1);
+    my_chip8.op_00cn(15);
+    my_chip8.op_00fb();
+    assert_eq!(my_chip8.pixel(104, 55), 1);
+    my_chip8.op_00cn(9);
+    assert_eq!(my_chip8.to_text('#', '.').matches('#').count(), 0);
+}
+
+#[test]
+fn test_planes() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.set_pixel(0, 0, 1);
+    my_chip8.memory[0x300..0x304].copy_from_slice(&[0xC0, 0x80, 0xF0, 0xF0]);
+    my_chip8.i = 0x300;
+
+    // the second plane only: the first is left alone
+    my_chip8.op_fn01(2);
+    assert_eq!((my_chip8.selected_planes, my_chip8.pc), (2, 0x202));
+    my_chip8.op_dxyn(0x0, 0x0, 1);
+    assert_eq!((my_chip8.pixel(0, 0), my_chip8.pixel(1, 0), my_chip8.v[0xf]), (3, 2, 0));
+
+    // both planes read consecutive sprites, and collide on either
+    my_chip8.op_fn01(3);
+    my_chip8.i = 0x301;
+    my_chip8.op_dxyn(0x0, 0x0, 1);
+    assert_eq!(my_chip8.sprite_source, Some((0x301, 2)));
+    assert_eq!((my_chip8.pixel(0, 0), my_chip8.pixel(1, 0), my_chip8.pixel(2, 0)), (0, 0, 2));
+    assert_eq!(my_chip8.v[0xf], 1);
+
+    let mut frame = vec![0; 64 * 32 * 4];
+    my_chip8.set_pixel(1, 0, 2);
+    my_chip8.set_pixel(2, 0, 3);
+    my_chip8.draw(&mut frame);
+    assert_eq!(frame[0..4], my_chip8.palette.background);
+    assert_eq!(frame[4..8], my_chip8.palette.second);
+    assert_eq!(frame[8..12], my_chip8.palette.blend);
+
+    // clearing and scrolling only touch the selected planes
+    my_chip8.op_fn01(1);
+    my_chip8.op_00e0();
+    assert_eq!((my_chip8.pixel(1, 0), my_chip8.pixel(2, 0)), (2, 2));
+    my_chip8.set_pixel(3, 0, 1);
+    my_chip8.op_fn01(2);
+    my_chip8.op_00cn(1);
+    assert_eq!((my_chip8.pixel(1, 1), my_chip8.pixel(2, 1), my_chip8.pixel(3, 0)), (2, 2, 1));
+    assert_eq!(my_chip8.pixel(2, 0), 0);
+}
+
+#[test]
+fn test_dxy0_draws_16x16_in_hires() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.hires = true;
+    my_chip8.i = 0x300;
+    // left column and top row lit, plus the bottom-right corner
+    for row in 0..16 {
+        my_chip8.memory[0x300 + row * 2] = 0x80;
+    }
+    my_chip8.memory[0x300] = 0xFF;
+    my_chip8.memory[0x301] = 0xFF;
+    my_chip8.memory[0x31F] = 0x01;
+    my_chip8.v[0x0] = 120;
+    my_chip8.v[0x1] = 10;
+
+    my_chip8.op_dxyn(0, 1, 0);
+    assert_eq!(my_chip8.v[0xF], 0);
+    assert_eq!(my_chip8.pixel(120, 25), 1);
+    assert_eq!(my_chip8.pixel(127, 10), 1);
+    // the right half of the top row wraps to the left edge
+    assert_eq!(my_chip8.pixel(7, 10), 1);
+    assert_eq!(my_chip8.pixel(7, 25), 1);
+    assert_eq!(my_chip8.pixel(121, 11), 0);
+    assert_eq!(my_chip8.sprite_source, Some((0x300, 32)));
+
+    // drawing it again erases it, with a collision
+    my_chip8.op_dxyn(0, 1, 0);
+    assert_eq!(my_chip8.v[0xF], 1);
+    assert_eq!(my_chip8.to_text('#', '.').matches('#').count(), 0);
+}
+
+#[test]
+fn test_dxy0_draws_nothing_in_lowres() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.memory[0] = 0xFF;
+    my_chip8.op_dxyn(0, 1, 0);
+    assert_eq!(my_chip8.to_text('#', '.').matches('#').count(), 0);
+}
+
+#[test]
+fn test_pause_and_step_once() {
+    let mut my_chip8 = Chip8::initialize();
+    // LD V0, 1; ADD V0, 1; ADD V0, 1
+    my_chip8.load_program_bytes(&[0x60, 0x01, 0x70, 0x01, 0x70, 0x01]).unwrap();
+    my_chip8.pause();
+    my_chip8.run_cycles(5);
+    assert_eq!(my_chip8.pc, 0x200);
+
+    // one instruction at a time, staying paused
+    assert_eq!(my_chip8.step_once(), Ok(()));
+    assert_eq!(my_chip8.step_once(), Ok(()));
+    assert_eq!((my_chip8.pc, my_chip8.v[0x0]), (0x204, 2));
+    assert!(my_chip8.paused);
+
+    my_chip8.resume();
+    my_chip8.run_cycles(1);
+    assert_eq!(my_chip8.v[0x0], 3);
+    // stepping a running machine leaves it running
+    assert_eq!(my_chip8.step_once(), Err(Chip8Error::UnknownOpcode(0x0000)));
+    assert!(!my_chip8.paused);
+}
+
+#[test]
+fn test_snapshot() {
+    let mut my_chip8 = Chip8::initialize();
+    // LD V3, 0x42; LD I, 0x300
+    my_chip8.load_program_bytes(&[0x63, 0x42, 0xA3, 0x00]).unwrap();
+    my_chip8.delay_timer = 9;
+    my_chip8.run_cycles(2);
+
+    let before = my_chip8.snapshot();
+    assert_eq!((before.pc, before.i, before.sp), (0x204, 0x300, 0));
+    assert_eq!((before.v[0x3], before.delay_timer, before.sound_timer), (0x42, 9, 0));
+    assert_eq!(before.opcode, 0xA300);
+
+    // a copy, unaffected by running on
+    my_chip8.v[0x3] = 0;
+    assert_eq!(before.v[0x3], 0x42);
+    assert_ne!(my_chip8.snapshot(), before);
+}
+
+#[test]
+fn test_breakpoint() {
+    let mut my_chip8 = Chip8::initialize();
+    // LD V0, 1; ADD V0, 1; ADD V0, 1; JP 0x206
+    my_chip8.load_program_bytes(&[0x60, 0x01, 0x70, 0x01, 0x70, 0x01, 0x12, 0x06]).unwrap();
+    my_chip8.add_breakpoint(0x204);
+    my_chip8.run_cycles(10);
+    assert!(my_chip8.paused);
+    assert_eq!(my_chip8.breakpoint_hit, Some(0x204));
+    assert_eq!((my_chip8.pc, my_chip8.v[0x0]), (0x204, 2));
+
+    // resuming runs the instruction at the breakpoint
+    my_chip8.resume();
+    my_chip8.run_cycles(1);
+    assert_eq!((my_chip8.pc, my_chip8.v[0x0], my_chip8.breakpoint_hit), (0x206, 3, None));
+
+    // it fires again the next time it's reached, unless removed
+    my_chip8.pc = 0x204;
+    my_chip8.run_cycles(1);
+    assert!(my_chip8.paused);
+    my_chip8.remove_breakpoint(0x204);
+    my_chip8.resume();
+    my_chip8.pc = 0x202;
+    my_chip8.run_cycles(2);
+    assert!(!my_chip8.paused);
+    assert_eq!(my_chip8.v[0x0], 5);
+}
+
+#[test]
+fn test_reset() {
+    let program = [0x60, 0x05, 0xA3, 0x00, 0x22, 0x08, 0x00, 0xFF, 0xF0, 0x15, 0xD0, 0x05, 0x12, 0x0A];
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.load_program_bytes(&program).unwrap();
+    my_chip8.key[0x3] = true;
+    my_chip8.memory[0x10] = 0x00;                       // a ROM writing over the font
+    my_chip8.run_cycles(10);
+    assert!(my_chip8.sp > 0 && my_chip8.delay_timer > 0);
+
+    my_chip8.reset();
+    let mut fresh = Chip8::initialize();
+    fresh.load_fontset();
+    fresh.load_program_bytes(&program).unwrap();
+    assert_eq!(my_chip8.snapshot(), fresh.snapshot());
+    assert_eq!((my_chip8.stack, my_chip8.key, my_chip8.hires), (fresh.stack, fresh.key, fresh.hires));
+    assert_eq!(my_chip8.gfx_hash(), fresh.gfx_hash());
+    assert!(my_chip8.verify_fontset());
+    assert_eq!(&my_chip8.memory[0x200..0x200 + program.len()], &program[..]);
+    assert!(my_chip8.draw_flag);
+}
+
+#[test]
+fn test_debug_and_dump_memory() {
+    let mut my_chip8 = Chip8::initialize();
+    // LD VA, 0x2A; CALL 0x300
+    my_chip8.load_program_bytes(&[0x6A, 0x2A, 0x23, 0x00]).unwrap();
+    my_chip8.delay_timer = 0x10;
+    my_chip8.run_cycles(2);
+    assert_eq!(format!("{:?}", my_chip8), "\
+PC 300  I 000  SP 1  DT 10  ST 00  opcode 2300
+V0 00  V1 00  V2 00  V3 00  V4 00  V5 00  V6 00  V7 00
+V8 00  V9 00  VA 2A  VB 00  VC 00  VD 00  VE 00  VF 00
+stack 204
+");
+
+    assert_eq!(my_chip8.dump_memory(0x1FE..0x212), "\
+1FE  00 00 6A 2A 23 00 00 00 00 00 00 00 00 00 00 00
+20E  00 00 00 00
+");
+    assert_eq!(my_chip8.dump_memory(0xFFE..0x2000), "FFE  00 00\n");
+}
+
+#[test]
+fn test_audio_pattern() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.sound_timer = 2;
+    assert_eq!(my_chip8.sound(), Tone::Buzzer);
+
+    let pattern: Vec<u8> = (0..16).collect();
+    my_chip8.memory[0x300..0x310].copy_from_slice(&pattern);
+    my_chip8.i = 0x300;
+    my_chip8.op_f002();
+    assert_eq!((&my_chip8.audio_buffer[..], my_chip8.pc), (&pattern[..], 0x202));
+
+    my_chip8.v[0x4] = DEFAULT_PITCH + 48;
+    my_chip8.op_fx3a(0x4);
+    assert_eq!(my_chip8.sound(), Tone::Pattern { bits: my_chip8.audio_buffer, rate: 8000.0 });
+
+    my_chip8.sound_timer = 0;
+    assert_eq!(my_chip8.sound(), Tone::Silent);
+}
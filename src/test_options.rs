@@ -0,0 +1,53 @@
+use clap::{CommandFactory, Parser};
+use chip8::quirks::Preset;
+use crate::options::Options;
+
+#[test]
+fn test_options_definition() {
+    Options::command().debug_assert();
+}
+
+#[test]
+fn test_defaults() {
+    let options = Options::try_parse_from(["chip8", "rom.ch8"]).unwrap();
+    assert_eq!(options.rom.as_deref(), Some("rom.ch8"));
+    assert_eq!(options.cycles, 10_000);
+    assert!(options.vsync && options.large_font);
+    assert_eq!(options.quirks, Preset::Modern);
+    assert_eq!((options.fg, options.bg), ([0xff, 0xff, 0xff, 0xff], [0x00, 0x00, 0x00, 0xff]));
+    assert_eq!(options.entry, 0x200);
+    assert_eq!(options.text_chars, ('#', '.'));
+}
+
+#[test]
+fn test_values() {
+    let options = Options::try_parse_from([
+        "chip8", "--vsync", "off", "--no-large-font", "--quirks", "schip", "--fg", "#ff8000",
+        "--break", "0x300", "--break-reg", "VA==1", "--break-mem", "0x300==5", "rom.ch8",
+    ]).unwrap();
+    assert!(!options.vsync && !options.large_font);
+    assert_eq!(options.quirks, Preset::Schip);
+    assert_eq!(options.fg, [0xff, 0x80, 0x00, 0xff]);
+    assert_eq!(options.breakpoints, [0x300]);
+    assert_eq!(options.watchpoints.len(), 2);
+
+    let options = Options::try_parse_from(["chip8", "--diff-trace", "a.ch8", "b.ch8"]).unwrap();
+    assert_eq!(options.diff_trace, Some(vec!["a.ch8".to_string(), "b.ch8".to_string()]));
+}
+
+#[test]
+fn test_rejected() {
+    for args in [
+        &["chip8"][..],
+        &["chip8", "--memory-image", "dump.bin", "rom.ch8"],
+        &["chip8", "--ipf", "0", "rom.ch8"],
+        &["chip8", "--scale", "65", "rom.ch8"],
+        &["chip8", "--scale", "4294967295", "rom.ch8"],
+        &["chip8", "--vsync", "maybe", "rom.ch8"],
+        &["chip8", "--break", "0x1000", "rom.ch8"],
+        &["chip8", "--no-such-option", "rom.ch8"],
+    ].iter() {
+        assert!(Options::try_parse_from(args.iter()).is_err(), "{:?}", args);
+    }
+    assert!(Options::try_parse_from(["chip8", "--memory-image", "dump.bin"]).is_ok());
+}
@@ -0,0 +1,19 @@
+use std::collections::BTreeMap;
+use crate::profile::to_csv;
+use crate::Chip8;
+
+#[test]
+fn test_profile_csv() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.profile = Some(BTreeMap::new());
+    // LD V0, 0x01; ADD V0, 0x01; JP 0x202
+    my_chip8.memory[0x200..0x206].copy_from_slice(&[0x60, 0x01, 0x70, 0x01, 0x12, 0x02]);
+    for _ in 0..7 {
+        my_chip8.emulate_cycle();
+    }
+
+    assert_eq!(
+        to_csv(my_chip8.profile.as_ref().unwrap()),
+        "class,mnemonic,count,percent\n1nnn,JP,3,42.86\n7xkk,ADD,3,42.86\n6xkk,LD,1,14.29\n"
+    );
+}
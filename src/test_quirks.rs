@@ -0,0 +1,174 @@
+use crate::Chip8;
+use crate::quirks::{IncrementI, Preset, Quirks};
+
+#[test]
+fn test_preset_cycle_wraps() {
+    assert_eq!(Preset::Modern.cycle(1), Preset::Cosmac);
+    assert_eq!(Preset::Cosmac.cycle(-1), Preset::Modern);
+    assert_eq!(Preset::Cosmac.cycle(1), Preset::Schip);
+}
+
+#[test]
+fn test_cycling_presets_updates_quirks() {
+    let mut my_chip8 = Chip8::initialize();
+    assert_eq!(my_chip8.quirks, Quirks::default());
+
+    let mut preset = Preset::Modern;
+    for expected in [Preset::Cosmac, Preset::Schip, Preset::XoChip, Preset::Modern].iter() {
+        preset = preset.cycle(1);
+        my_chip8.quirks = preset.quirks();
+        assert_eq!(preset, *expected);
+        assert_eq!(my_chip8.quirks, expected.quirks());
+    }
+}
+
+#[test]
+fn test_logic_resets_vf() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.v[0xf] = 1;
+    my_chip8.op_8xy1(0x0, 0x1);
+    assert_eq!(my_chip8.v[0xf], 1);
+
+    my_chip8.quirks = Preset::Cosmac.quirks();
+    my_chip8.op_8xy1(0x0, 0x1);
+    assert_eq!(my_chip8.v[0xf], 0);
+}
+
+#[test]
+fn test_logic_resets_vf_all_logic_ops() {
+    let ops: [fn(&mut Chip8, usize, usize); 3] = [Chip8::op_8xy1, Chip8::op_8xy2, Chip8::op_8xy3];
+    for (n, op) in ops.iter().enumerate() {
+        for &resets in [false, true].iter() {
+            let mut my_chip8 = Chip8::initialize();
+            my_chip8.quirks.logic_resets_vf = resets;
+            my_chip8.v[0x1] = 0x0C;
+            my_chip8.v[0x2] = 0x0A;
+            my_chip8.v[0xf] = 1;
+            op(&mut my_chip8, 0x1, 0x2);
+            assert_eq!(my_chip8.v[0x1], [0x0E, 0x08, 0x06][n], "8xy{} result", n + 1);
+            assert_eq!(my_chip8.v[0xf], if resets { 0 } else { 1 }, "8xy{} with logic_resets_vf {}", n + 1, resets);
+        }
+    }
+}
+
+#[test]
+fn test_load_store_increments_i() {
+    let cases = [
+        (IncrementI::Unchanged, 0x300),
+        (IncrementI::ByX, 0x303),
+        (IncrementI::ByXPlusOne, 0x304),
+    ];
+    for &(increment, expected) in cases.iter() {
+        let mut my_chip8 = Chip8::initialize();
+        my_chip8.quirks.load_store_increment = increment;
+        my_chip8.i = 0x300;
+        my_chip8.op_fx55(0x3);
+        assert_eq!(my_chip8.i, expected, "Fx55 with {:?}", increment);
+
+        my_chip8.i = 0x300;
+        my_chip8.op_fx65(0x3);
+        assert_eq!(my_chip8.i, expected, "Fx65 with {:?}", increment);
+    }
+}
+
+#[test]
+fn test_jump_uses_vx() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.v[0x0] = 0x01;
+    my_chip8.v[0x3] = 0x10;
+    my_chip8.op_bnnn(0x345);
+    assert_eq!(my_chip8.pc, 0x346);
+
+    my_chip8.quirks = Preset::Schip.quirks();
+    my_chip8.op_bnnn(0x345);
+    assert_eq!(my_chip8.pc, 0x355);
+}
+
+#[test]
+fn test_shift_in_place() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Schip.quirks());
+    my_chip8.v[0x1] = 0x81;
+    my_chip8.v[0x2] = 0x0F;
+    my_chip8.op_8x06(0x1, 0x2);
+    assert_eq!((my_chip8.v[0x1], my_chip8.v[0xf]), (0x40, 1));
+
+    my_chip8.v[0x1] = 0x81;
+    my_chip8.op_8x0e(0x1, 0x2);
+    assert_eq!((my_chip8.v[0x1], my_chip8.v[0xf]), (0x02, 1));
+    assert_eq!(my_chip8.v[0x2], 0x0F);
+}
+
+#[test]
+fn test_shift_uses_vy() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Cosmac.quirks());
+    assert!(my_chip8.quirks.shift_uses_vy);
+    my_chip8.v[0x1] = 0x81;
+    my_chip8.v[0x2] = 0x0E;
+    my_chip8.op_8x06(0x1, 0x2);
+    assert_eq!((my_chip8.v[0x1], my_chip8.v[0xf]), (0x07, 0));
+
+    my_chip8.v[0x2] = 0x90;
+    my_chip8.op_8x0e(0x1, 0x2);
+    assert_eq!((my_chip8.v[0x1], my_chip8.v[0xf]), (0x20, 1));
+    assert_eq!(my_chip8.v[0x2], 0x90);
+}
+
+#[test]
+fn test_sprite_wrap_per_axis() {
+    for &(wrap_x, wrap_y) in [(false, false), (true, false), (false, true), (true, true)].iter() {
+        let mut my_chip8 = Chip8::initialize();
+        my_chip8.quirks.wrap_x = wrap_x;
+        my_chip8.quirks.wrap_y = wrap_y;
+
+        // a solid 8x4 sprite at (60, 30) runs off the right and bottom edges
+        my_chip8.memory[0x300..0x304].copy_from_slice(&[0xFF; 4]);
+        my_chip8.i = 0x300;
+        my_chip8.v[0x0] = 60;
+        my_chip8.v[0x1] = 30;
+        my_chip8.op_dxyn(0x0, 0x1, 4);
+
+        let lit = |x: usize, y: usize| my_chip8.pixel(x, y) == 1;
+        let case = format!("wrap_x {} wrap_y {}", wrap_x, wrap_y);
+        assert!(lit(63, 31), "{}", case);
+        assert_eq!(lit(0, 30), wrap_x, "{}", case);
+        assert_eq!(lit(60, 0), wrap_y, "{}", case);
+        assert_eq!(lit(3, 1), wrap_x && wrap_y, "{}", case);
+        assert!(!lit(4, 30) && !lit(60, 2), "{}", case);
+    }
+}
+
+#[test]
+fn test_sprite_start_always_wraps() {
+    let mut my_chip8 = Chip8::with_quirks(Preset::Cosmac.quirks());
+    my_chip8.memory[0x300] = 0x80;
+    my_chip8.i = 0x300;
+    my_chip8.v[0x0] = 64 + 5;
+    my_chip8.v[0x1] = 32 + 7;
+    my_chip8.op_dxyn(0x0, 0x1, 1);
+    assert_eq!(my_chip8.pixel(5, 7), 1);
+}
+
+#[test]
+fn test_fx1e_wraps() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.i = 0xFFFF;
+    my_chip8.v[0x0] = 0x02;
+    my_chip8.v[0xf] = 0x07;
+    my_chip8.op_fx1e(0x0);
+    assert_eq!(my_chip8.i, 0x0001);
+    assert_eq!(my_chip8.v[0xf], 0x07);
+}
+
+#[test]
+fn test_fx1e_sets_vf() {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.quirks.fx1e_sets_vf = true;
+    my_chip8.i = 0x0FFE;
+    my_chip8.v[0x1] = 0x03;
+    my_chip8.op_fx1e(0x1);
+    assert_eq!((my_chip8.i, my_chip8.v[0xf]), (0x1001, 1));
+
+    my_chip8.i = 0x0100;
+    my_chip8.op_fx1e(0x1);
+    assert_eq!((my_chip8.i, my_chip8.v[0xf]), (0x0103, 0));
+}
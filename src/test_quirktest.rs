@@ -0,0 +1,56 @@
+use crate::Chip8;
+use crate::quirktest::{read, summary, Outcome};
+
+// a results screen recorded with to_text('#', '.') from the test suite's
+// combined ROM, run headless for 500000 cycles with the quirks test and the
+// CHIP-8 platform preselected
+fn screen(recorded: &str) -> Chip8 {
+    let mut my_chip8 = Chip8::initialize();
+    for (y, line) in recorded.lines().enumerate() {
+        for (x, c) in line.chars().enumerate() {
+            my_chip8.set_pixel(x, y, (c == '#') as u8);
+        }
+    }
+    my_chip8
+}
+
+#[test]
+fn test_read_cosmac_results() {
+    let results = read(&screen(include_str!("test_quirktest_cosmac.txt")));
+    assert_eq!(results, vec![
+        ("vF reset",     Outcome::Pass),
+        ("Memory",       Outcome::Pass),
+        ("Display wait", Outcome::Fail),
+        ("Clipping",     Outcome::Pass),
+        ("Shifting",     Outcome::Pass),
+        ("Jumping",      Outcome::Pass),
+    ]);
+    assert_eq!(summary(&results), "\
+vF reset      pass
+Memory        pass
+Display wait  FAIL
+Clipping      pass
+Shifting      pass
+Jumping       pass
+");
+}
+
+#[test]
+fn test_read_modern_results() {
+    let results = read(&screen(include_str!("test_quirktest_modern.txt")));
+    assert_eq!(results, vec![
+        ("vF reset",     Outcome::Fail),
+        ("Memory",       Outcome::Fail),
+        ("Display wait", Outcome::Fail),
+        ("Clipping",     Outcome::Fail),
+        ("Shifting",     Outcome::Fail),
+        ("Jumping",      Outcome::Pass),
+    ]);
+}
+
+#[test]
+fn test_read_unfinished() {
+    let results = read(&Chip8::initialize());
+    assert!(results.iter().all(|&(_, outcome)| outcome == Outcome::Unknown));
+    assert!(summary(&results).lines().all(|line| line.ends_with("no result")));
+}
@@ -0,0 +1,67 @@
+use crate::Chip8;
+use crate::replay::{run_frame, Recording, Timeline, KEYFRAME_INTERVAL};
+
+// a ROM whose state depends on both the keys and the RNG: each frame it adds
+// a random byte to V1 when key 5 is held, and draws V1's low digit
+fn machine() -> Chip8 {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    let rom = [
+        0x00, 0xE0, // CLS
+        0x60, 0x05, // LD V0, 5
+        0xE0, 0xA1, // SKNP V0
+        0x71, 0x01, // ADD V1, 1
+        0xC2, 0xFF, // RND V2, FF
+        0x81, 0x24, // ADD V1, V2
+        0xF1, 0x29, // LD F, V1
+        0xD3, 0x35, // DRW V3, V3, 5
+        0x12, 0x00, // JP 200
+    ];
+    my_chip8.memory[0x200..0x200 + rom.len()].copy_from_slice(&rom);
+    my_chip8
+}
+
+fn recording() -> Recording {
+    let mut recording = Recording::new(42);
+    recording.frames = (0..200).map(|frame| if frame % 7 < 3 { 1 << 5 } else { 0 }).collect();
+    recording
+}
+
+#[test]
+fn test_recording_round_trips_as_text() {
+    let mut recording = Recording::new(7);
+    let mut key = [false; 16];
+    key[0x5] = true;
+    key[0xF] = true;
+    recording.push(&key);
+    recording.push(&[false; 16]);
+    assert_eq!(recording.frames, vec![0x8020, 0x0000]);
+
+    let path = std::env::temp_dir().join(format!("chip8-session-{}.rec", std::process::id()));
+    recording.save(&path).unwrap();
+    assert_eq!(Recording::load(&path).unwrap(), recording);
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(Recording::parse("0000\n").is_err());
+}
+
+#[test]
+fn test_seek_matches_straight_playback() {
+    let mut scrubbed = machine();
+    let timeline = Timeline::build(&mut scrubbed, recording());
+
+    // forwards and backwards, on and off keyframes
+    for &frame in [130, 0, KEYFRAME_INTERVAL, 199, 61, 200].iter() {
+        let mut straight = machine();
+        straight.seed(timeline.recording.seed);
+        for &mask in timeline.recording.frames[..frame].iter() {
+            run_frame(&mut straight, mask);
+        }
+
+        assert_eq!(timeline.seek(&mut scrubbed, frame), frame);
+        assert!(scrubbed.rewind_state() == straight.rewind_state(), "state differs at frame {}", frame);
+    }
+
+    // past the end clamps to the last frame
+    assert_eq!(timeline.seek(&mut scrubbed, 1000), 200);
+}
@@ -0,0 +1,21 @@
+use std::env;
+use std::fs;
+use crate::rpl;
+
+#[test]
+fn test_rpl_round_trip() {
+    let dir = env::temp_dir().join(format!("chip8-rpl-{}", std::process::id()));
+    let rom_hash = 0x0123_4567_89ab_cdef;
+
+    // nothing saved yet
+    assert_eq!(rpl::load(&dir, rom_hash), [0; 8]);
+
+    let flags = [1, 2, 3, 4, 5, 6, 7, 8];
+    rpl::save(&dir, rom_hash, &flags).unwrap();
+    assert_eq!(rpl::load(&dir, rom_hash), flags);
+
+    // other ROMs keep their own flags
+    assert_eq!(rpl::load(&dir, rom_hash + 1), [0; 8]);
+
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,27 @@
+use std::time::Duration;
+use crate::sandbox::{run_sandboxed, SandboxError};
+
+#[test]
+fn test_sandbox_cycle_limit() {
+    // JP 0x200 forever
+    let rom = [0x12, 0x00];
+    let result = run_sandboxed(&rom, 1000, Duration::from_secs(10));
+    assert_eq!(result, Err(SandboxError::CycleLimit(1000)));
+}
+
+#[test]
+fn test_sandbox_returns_display() {
+    // LD F, V0; DRW V0, V0, 5; EXIT
+    let rom = [0xf0, 0x29, 0xd0, 0x05, 0x00, 0xfd];
+    let display = run_sandboxed(&rom, 1000, Duration::from_secs(10)).unwrap();
+    assert_eq!(display.len(), 64 * 32);
+    // top row of the '0' glyph
+    assert_eq!(&display[0..5], &[1, 1, 1, 1, 0]);
+}
+
+#[test]
+fn test_sandbox_rom_too_large() {
+    let rom = vec![0; 4096];
+    let result = run_sandboxed(&rom, 1000, Duration::from_secs(10));
+    assert_eq!(result, Err(SandboxError::RomTooLarge { size: 4096, max: 3584 }));
+}
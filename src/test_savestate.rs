@@ -0,0 +1,76 @@
+use crate::processor::Chip8Error;
+use crate::Chip8;
+
+// draws, loops on a random number and counts down the delay timer, so the
+// state covers memory, display, rng and timers
+const PROGRAM: [u8; 16] = [
+    0x60, 0x30, // LD V0, 0x30
+    0xF0, 0x15, // LD DT, V0
+    0xA0, 0x00, // LD I, 0x000
+    0xC1, 0xFF, // RND V1, 0xFF
+    0xD1, 0x25, // DRW V1, V2, 5
+    0x72, 0x01, // ADD V2, 1
+    0xF1, 0x33, // LD B, V1 (I = 0x000)
+    0x12, 0x06, // JP 0x206
+];
+
+fn machine() -> Chip8 {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.load_program_bytes(&PROGRAM).unwrap();
+    my_chip8
+}
+
+#[test]
+fn test_save_state_round_trip() {
+    let mut my_chip8 = machine();
+    my_chip8.run_cycles(40);
+    my_chip8.key[0x7] = true;
+    my_chip8.hires = true;
+    let saved = my_chip8.save_state();
+
+    my_chip8.run_cycles(40);
+    let expected = (my_chip8.v, my_chip8.memory, my_chip8.gfx, my_chip8.pc);
+
+    // a fresh machine resumes from the save and ends up in the same place
+    let mut restored = Chip8::initialize();
+    restored.load_state(&saved).unwrap();
+    assert!(restored.key[0x7]);
+    assert!(restored.hires);
+    assert_eq!(restored.save_state(), saved);
+    restored.run_cycles(40);
+    assert_eq!((restored.v, restored.memory, restored.gfx, restored.pc), expected);
+}
+
+#[test]
+fn test_load_state_rejects_bad_data() {
+    let saved = machine().save_state();
+    let mut my_chip8 = machine();
+    my_chip8.run_cycles(10);
+    let before = my_chip8.save_state();
+
+    let mut trailing = saved.clone();
+    trailing.push(0);
+    let mut bad_sp = machine();
+    bad_sp.sp = 17;
+    let bad_sp = bad_sp.save_state();
+
+    for (data, reason) in [
+        (&saved[..saved.len() - 1], "truncated"),
+        (&b"CH8S"[..], "truncated"),
+        (&trailing[..], "not a save state"),
+        (&bad_sp[..], "stack pointer out of range"),
+    ].iter() {
+        assert_eq!(my_chip8.load_state(data), Err(Chip8Error::BadState(reason)));
+        assert_eq!(my_chip8.save_state(), before);
+    }
+}
+
+#[test]
+fn test_serde_round_trip() {
+    let mut my_chip8 = machine();
+    my_chip8.run_cycles(40);
+    let json = serde_json::to_string(&my_chip8).unwrap();
+    let restored: Chip8 = serde_json::from_str(&json).unwrap();
+    assert_eq!(restored.save_state(), my_chip8.save_state());
+}
@@ -0,0 +1,78 @@
+use crate::Chip8;
+use crate::quirks::Preset;
+use crate::testgen::{capture, to_rust};
+
+// runs through most instruction classes once, then spins
+const ROM: [u8; 70] = [
+    0x00, 0xE0,                             // 200: CLS
+    0x6A, 0x05,                             // 202: LD VA, 5
+    0x6B, 0x0C,                             // 204: LD VB, 12
+    0x7A, 0x03,                             // 206: ADD VA, 3
+    0x8C, 0xA0,                             // 208: LD VC, VA
+    0x8C, 0xB1,                             // 20A: OR VC, VB
+    0x8C, 0xB2,                             // 20C: AND VC, VB
+    0x8C, 0xB3,                             // 20E: XOR VC, VB
+    0x8A, 0xB4,                             // 210: ADD VA, VB
+    0x8A, 0xB5,                             // 212: SUB VA, VB
+    0x8A, 0x06,                             // 214: SHR VA
+    0x8A, 0xB7,                             // 216: SUBN VA, VB
+    0x8A, 0x0E,                             // 218: SHL VA
+    0x3A, 0x10,                             // 21A: SE VA, 16
+    0x1F, 0xFF,                             // 21C: JP FFF (skipped)
+    0x4A, 0x10,                             // 21E: SNE VA, 16
+    0x5A, 0xB0,                             // 220: SE VA, VB
+    0x9A, 0xB0,                             // 222: SNE VA, VB
+    0x1F, 0xFF,                             // 224: JP FFF (skipped)
+    0xA3, 0x00,                             // 226: LD I, 300
+    0xFA, 0x33,                             // 228: LD B, VA
+    0xF2, 0x65,                             // 22A: LD V2, [I]
+    0xF2, 0x29,                             // 22C: LD F, V2
+    0xD0, 0x15,                             // 22E: DRW V0, V1, 5
+    0x22, 0x40,                             // 230: CALL 240
+    0xF0, 0x1E,                             // 232: ADD I, V0
+    0x62, 0x05,                             // 234: LD V2, 5
+    0xF2, 0x15,                             // 236: LD DT, V2
+    0xF3, 0x07,                             // 238: LD V3, DT
+    0xF2, 0x18,                             // 23A: LD ST, V2
+    0xB2, 0x3E,                             // 23C: JP V0, 23E
+    0x12, 0x3E,                             // 23E: JP 23E
+    0x6E, 0x01,                             // 240: LD VE, 1
+    0xFE, 0x55,                             // 242: LD [I], VE
+    0x00, 0xEE,                             // 244: RET
+];
+
+fn sample_run() -> Chip8 {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.memory[0x200..0x200 + ROM.len()].copy_from_slice(&ROM);
+    my_chip8
+}
+
+// test names in generated code, in order
+fn test_names(code: &str) -> Vec<&str> {
+    code.lines()
+        .filter_map(|line| line.strip_prefix("fn test_gen_"))
+        .map(|rest| rest.trim_end_matches("() {"))
+        .collect()
+}
+
+// test_generated.rs was generated from this run and runs as part of the
+// suite. the run must still produce the same tests, but the text may drift
+// as the generator's output changes, so only the tests it holds are compared
+#[test]
+fn test_generated_file_is_current() {
+    let samples = capture(&mut sample_run(), 100, Preset::Modern);
+    let classes: Vec<String> = samples.iter().map(|sample| sample.class.to_lowercase()).collect();
+    assert_eq!(classes.len(), 30, "{:?}", classes);
+    assert_eq!(test_names(&to_rust(&samples, Preset::Modern)), classes);
+    assert_eq!(test_names(include_str!("test_generated.rs")), classes);
+}
+
+#[test]
+fn test_one_sample_per_class() {
+    let samples = capture(&mut sample_run(), 100, Preset::Modern);
+    let mut classes: Vec<&str> = samples.iter().map(|sample| sample.class).collect();
+    classes.dedup();
+    assert_eq!(classes.len(), samples.len());
+    assert!(!classes.contains(&"Cxkk"));
+}
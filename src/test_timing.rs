@@ -0,0 +1,141 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use winit::event_loop::ControlFlow;
+use chip8::processor::TIMER_PERIOD;
+use chip8::Chip8;
+use crate::timing::{frame_throttle, next_timer_deadline, step_speed, tick_control_flow, MAX_SPEED, MIN_SPEED};
+use crate::timing::{Clock, MockClock, TimerTicker};
+
+#[test]
+fn test_next_timer_deadline() {
+    let start = Instant::now();
+    assert_eq!(next_timer_deadline(start, start), start + TIMER_PERIOD);
+
+    // a loop that fell several ticks behind resumes from now
+    let late = start + TIMER_PERIOD * 5;
+    assert_eq!(next_timer_deadline(start, late), late + TIMER_PERIOD);
+}
+
+#[test]
+fn test_eco_waits_for_next_tick() {
+    // eco mode runs a batch when a tick is due, then sleeps until the tick
+    // after it; input waking the loop early runs nothing
+    let clock = MockClock::new();
+    let mut ticker = TimerTicker::new(&clock);
+    let mut my_chip8 = Chip8::initialize();
+    // ADD V0, 0x01 then JP 0x200
+    my_chip8.memory[0x200..0x204].copy_from_slice(&[0x70, 0x01, 0x12, 0x00]);
+    let batch = my_chip8.cycles_per_frame();
+
+    clock.advance(TIMER_PERIOD);
+    let last_tick = clock.now();
+    assert!(ticker.due(&clock));
+    my_chip8.run_cycles(batch);
+    assert_eq!(tick_control_flow(ticker.deadline()), ControlFlow::WaitUntil(last_tick + TIMER_PERIOD));
+
+    clock.advance(TIMER_PERIOD / 3);
+    if ticker.due(&clock) {
+        my_chip8.run_cycles(batch);
+    }
+    assert_eq!(my_chip8.instructions, batch as u64);
+    assert_eq!(ticker.deadline(), last_tick + TIMER_PERIOD);
+}
+
+#[test]
+fn test_step_speed_clamps() {
+    assert_eq!(step_speed(500, 1), 550);
+    assert_eq!(step_speed(500, -1), 450);
+    assert_eq!(step_speed(MIN_SPEED, -1), MIN_SPEED);
+    assert_eq!(step_speed(MAX_SPEED, 1), MAX_SPEED);
+}
+
+#[test]
+fn test_frame_throttle() {
+    let start = Instant::now();
+    let min = Duration::from_millis(10);
+    assert_eq!(frame_throttle(start, start + Duration::from_millis(3), min), Duration::from_millis(7));
+    assert_eq!(frame_throttle(start, start + Duration::from_millis(12), min), Duration::ZERO);
+}
+
+#[test]
+fn test_frame_throttle_wall_time() {
+    let min = Duration::from_millis(5);
+    let start = Instant::now();
+    let mut last_present = start;
+    for _ in 0..3 {
+        thread::sleep(frame_throttle(last_present, Instant::now(), min));
+        last_present = Instant::now();
+    }
+    assert!(start.elapsed() >= min * 3);
+}
+
+#[test]
+fn test_timer_ticks_once_per_period() {
+    let clock = MockClock::new();
+    let mut ticker = TimerTicker::new(&clock);
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.delay_timer = 5;
+
+    clock.advance(TIMER_PERIOD / 2);
+    assert!(!ticker.due(&clock));
+
+    clock.advance(TIMER_PERIOD / 2);
+    while ticker.due(&clock) {
+        my_chip8.tick_timers();
+    }
+    assert_eq!(my_chip8.delay_timer, 4);
+}
+
+#[test]
+fn test_timers_tick_at_60hz_without_instructions() {
+    // a second of wall time with no instructions run: both timers count
+    // down once per tick, each stopping at zero on its own
+    let clock = MockClock::new();
+    let mut ticker = TimerTicker::new(&clock);
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.delay_timer = 100;
+    my_chip8.sound_timer = 30;
+
+    for _ in 0..60 {
+        clock.advance(TIMER_PERIOD);
+        while ticker.due(&clock) {
+            my_chip8.tick_timers();
+        }
+    }
+    assert_eq!((my_chip8.delay_timer, my_chip8.sound_timer), (40, 0));
+    assert_eq!(my_chip8.instructions, 0);
+}
+
+#[test]
+fn test_cycle_pacing_waits() {
+    // 150 instructions per second leaves most of a 6.67ms cycle to sleep
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.clock_speed = 150;
+    let start = Instant::now();
+    let wait = frame_throttle(start, start + Duration::from_millis(1), my_chip8.cycle_time());
+    assert_eq!(wait, Duration::from_nanos(1_000_000_000 / 150) - Duration::from_millis(1));
+}
+
+#[test]
+fn test_cycles_per_frame() {
+    // a second of ticks runs ipf instructions per tick, whatever happens
+    // between them
+    let clock = MockClock::new();
+    let mut ticker = TimerTicker::new(&clock);
+    let mut my_chip8 = Chip8::initialize();
+    // ADD V0, 0x01 then JP 0x200
+    my_chip8.memory[0x200..0x204].copy_from_slice(&[0x70, 0x01, 0x12, 0x00]);
+    my_chip8.set_cycles_per_frame(11);
+    assert_eq!((my_chip8.clock_speed, my_chip8.cycles_per_frame()), (660, 11));
+
+    for _ in 0..60 {
+        assert_eq!(ticker.deadline(), clock.now() + TIMER_PERIOD);
+        clock.advance(TIMER_PERIOD / 4);
+        assert!(!ticker.due(&clock));
+        clock.advance(TIMER_PERIOD - TIMER_PERIOD / 4);
+        while ticker.due(&clock) {
+            my_chip8.run_cycles(my_chip8.cycles_per_frame());
+        }
+    }
+    assert_eq!(my_chip8.instructions, 660);
+}
@@ -0,0 +1,68 @@
+use crate::Chip8;
+use crate::trace::{diff_traces, TraceEntry};
+
+fn load(program: &[u8]) -> Chip8 {
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.seed(0);
+    my_chip8.memory[0x200..0x200 + program.len()].copy_from_slice(program);
+    my_chip8
+}
+
+#[test]
+fn test_diff_traces_identical() {
+    let program = [0x60, 0x01, 0x61, 0x02, 0x80, 0x14, 0x12, 0x06];
+    let mut a = load(&program);
+    let mut b = load(&program);
+
+    assert!(diff_traces(&mut a, &mut b, 50, 3).is_none());
+}
+
+#[test]
+fn test_diff_traces_divergence() {
+    // identical apart from the immediate loaded into V1 at 0x202
+    let program_a = [0x60, 0x01, 0x61, 0x02, 0x62, 0x03, 0x80, 0x14, 0x12, 0x08];
+    let program_b = [0x60, 0x01, 0x61, 0x05, 0x62, 0x03, 0x80, 0x14, 0x12, 0x08];
+    let mut a = load(&program_a);
+    let mut b = load(&program_b);
+
+    let divergence = diff_traces(&mut a, &mut b, 50, 3).expect("traces should diverge");
+    assert_eq!(divergence.cycle, 1);
+    assert_eq!(divergence.a.pc, 0x202);
+    assert_eq!(divergence.a.opcode, 0x6102);
+    assert_eq!(divergence.b.opcode, 0x6105);
+    assert_eq!(divergence.context.len(), 1);
+    assert_eq!(divergence.context[0].pc, 0x200);
+}
+
+#[test]
+fn test_diff_traces_same_seed() {
+    // RND V0, 0xff is reproducible when both machines share a seed
+    let program = [0xc0, 0xff, 0x12, 0x00];
+    let mut a = load(&program);
+    let mut b = load(&program);
+
+    assert!(diff_traces(&mut a, &mut b, 20, 3).is_none());
+}
+
+#[test]
+fn test_trace_ring_buffer() {
+    // LD V0, 1; ADD V0, 1; JP 0x202
+    let mut my_chip8 = load(&[0x60, 0x01, 0x70, 0x01, 0x12, 0x02]);
+    my_chip8.trace_len = 4;
+    my_chip8.run_cycles(3);
+    let trace = my_chip8.trace();
+    assert_eq!(trace.len(), 3);
+    assert_eq!(trace[0], TraceEntry { pc: 0x200, opcode: 0x6001, mnemonic: "LD" });
+    assert_eq!(trace[2].to_string(), "0x204  1202  JP");
+
+    // only the most recent instructions are kept
+    my_chip8.run_cycles(20);
+    let trace = my_chip8.trace();
+    assert_eq!(trace.len(), 4);
+    assert_eq!(trace.iter().map(|entry| entry.pc).collect::<Vec<_>>(), [0x202, 0x204, 0x202, 0x204]);
+
+    my_chip8.trace_len = 0;
+    my_chip8.run_cycles(1);
+    assert!(my_chip8.trace().is_empty());
+}
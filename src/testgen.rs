@@ -0,0 +1,275 @@
+use std::collections::BTreeSet;
+use std::fmt::Write;
+use crate::instruction::DecodedInstruction;
+use crate::processor::Chip8;
+use crate::quirks::Preset;
+use crate::{WIDTH, HEIGHT};
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_testgen.rs"]
+mod test_testgen;
+#[cfg(test)]
+#[path = "test_generated.rs"]
+mod test_generated;
+
+// opcode-level regression tests generated from a real run: the state before
+// an instruction, the instruction, and the state after it, for one execution
+// of each instruction class
+
+// Cxkk depends on the random source, which a generated test can't set up
+const SKIPPED_CLASSES: [&str; 2] = ["Cxkk", "????"];
+
+// the parts of the machine a generated test sets up or checks
+#[derive(Clone)]
+struct State {
+    v:           [u8; 16],
+    i:           u16,
+    pc:          u16,
+    sp:          usize,
+    stack:       [u16; 16],
+    delay_timer: u8,
+    sound_timer: u8,
+    key:         [bool; 16],
+    memory:      [u8; 4096],
+    gfx:         [[u8; 128]; 64],
+    hires:       bool,
+    selected_planes: u8,
+}
+
+impl State {
+    fn of(chip8: &Chip8) -> Self {
+        Self {
+            v:           chip8.v,
+            i:           chip8.i,
+            pc:          chip8.pc,
+            sp:          chip8.sp,
+            stack:       chip8.stack,
+            delay_timer: chip8.delay_timer,
+            sound_timer: chip8.sound_timer,
+            key:         chip8.key,
+            memory:      chip8.memory,
+            gfx:         chip8.gfx,
+            hires:       chip8.hires,
+            selected_planes: chip8.selected_planes,
+        }
+    }
+
+    // addresses written by the instruction that led from `before` to here
+    fn writes(&self, before: &State) -> Vec<usize> {
+        (0..self.memory.len()).filter(|&addr| self.memory[addr] != before.memory[addr]).collect()
+    }
+}
+
+// one captured execution, with the setup a test needs to repeat it
+pub struct Sample {
+    pub class:  &'static str,
+    pub opcode: u16,
+    pre:        State,                      // the real run, before the instruction
+    fresh:      State,                      // the test's setup, before the instruction
+    post:       State,                      // the test's setup, after the instruction
+    reads:      Option<(usize, usize)>,    // memory the instruction reads, start and end
+    cells:      Vec<(usize, usize)>,       // display cells the instruction may change
+}
+
+impl Sample {
+
+    fn new(pre: State, preset: Preset) -> Option<Self> {
+        // generated tests only set up the 64x32 display, drawn on one plane
+        let pc = pre.pc as usize & 0xFFF;
+        if pc + 2 > pre.memory.len() || pre.hires || pre.selected_planes != 1 {
+            return None;
+        }
+        let opcode = (pre.memory[pc] as u16) << 8 | pre.memory[pc + 1] as u16;
+        let decoded = DecodedInstruction::new(opcode);
+        let i = pre.i as usize;
+        let (reads, cells) = match decoded.class {
+            "Dxyn" => {
+                let (x0, y0) = (pre.v[decoded.x] as usize, pre.v[decoded.y] as usize);
+                let cells = (0..decoded.n)
+                    .flat_map(|row| (0..8).map(move |col| ((x0 + col) % WIDTH as usize, (y0 + row) % HEIGHT as usize)))
+                    .collect();
+                (Some((i, i + decoded.n)), cells)
+            }
+            "Fx65" => (Some((i, i + decoded.x + 1)), Vec::new()),
+            _ => (None, Vec::new()),
+        };
+        if matches!(reads, Some((_, end)) if end > pre.memory.len()) {
+            return None;
+        }
+
+        let mut sample = Self { class: decoded.class, opcode, fresh: pre.clone(), post: pre.clone(), pre, reads, cells };
+        let mut my_chip8 = sample.setup(preset);
+        sample.fresh = State::of(&my_chip8);
+        my_chip8.emulate_cycle();
+        sample.post = State::of(&my_chip8);
+        Some(sample)
+    }
+
+    // a fresh machine holding only the state the generated test sets up
+    fn setup(&self, preset: Preset) -> Chip8 {
+        let mut my_chip8 = Chip8::with_quirks(preset.quirks());
+        let pc = self.pre.pc as usize & 0xFFF;
+        my_chip8.pc = self.pre.pc;
+        my_chip8.memory[pc..pc + 2].copy_from_slice(&self.pre.memory[pc..pc + 2]);
+        if let Some((start, end)) = self.reads {
+            my_chip8.memory[start..end].copy_from_slice(&self.pre.memory[start..end]);
+        }
+        my_chip8.v = self.pre.v;
+        my_chip8.i = self.pre.i;
+        my_chip8.sp = self.pre.sp;
+        my_chip8.stack[..self.pre.sp].copy_from_slice(&self.pre.stack[..self.pre.sp]);
+        my_chip8.delay_timer = self.pre.delay_timer;
+        my_chip8.sound_timer = self.pre.sound_timer;
+        my_chip8.key = self.pre.key;
+        for &(x, y) in self.lit_cells(&self.pre).iter() {
+            my_chip8.set_pixel(x, y, 1);
+        }
+        my_chip8
+    }
+
+    fn lit_cells(&self, state: &State) -> Vec<(usize, usize)> {
+        self.cells.iter().copied().filter(|&(x, y)| state.gfx[y][x] != 0).collect()
+    }
+
+    // whether the fresh machine ended up where the real run did, so the
+    // generated assertions describe the ROM's actual behaviour
+    fn matches(&self, real: &State) -> bool {
+        let post = &self.post;
+        post.v == real.v && post.i == real.i && post.pc == real.pc && post.sp == real.sp
+            && post.stack[..post.sp] == real.stack[..real.sp]
+            && post.delay_timer == real.delay_timer && post.sound_timer == real.sound_timer
+            && post.writes(&self.fresh).iter().chain(real.writes(&self.pre).iter())
+                .all(|&addr| post.memory[addr] == real.memory[addr])
+            && self.lit_cells(post) == self.lit_cells(real)
+            && (self.class != "00E0" || lit(&post.gfx) == lit(&real.gfx))
+    }
+
+    fn to_rust(&self, preset: Preset) -> String {
+        let (pre, post) = (&self.pre, &self.post);
+        let mut out = String::new();
+        writeln!(out, "#[test]").unwrap();
+        writeln!(out, "fn test_gen_{}() {{", self.class.to_lowercase()).unwrap();
+        writeln!(out, "    let mut my_chip8 = Chip8::with_quirks(Preset::{:?}.quirks());", preset).unwrap();
+        writeln!(out, "    my_chip8.pc = {:#05x};", pre.pc).unwrap();
+        let pc = pre.pc as usize & 0xFFF;
+        writeln!(out, "    my_chip8.memory[{:#05x}..{:#05x}].copy_from_slice(&{});", pc, pc + 2, bytes(&pre.memory[pc..pc + 2])).unwrap();
+        if let Some((start, end)) = self.reads {
+            writeln!(out, "    my_chip8.memory[{:#05x}..{:#05x}].copy_from_slice(&{});", start, end, bytes(&pre.memory[start..end])).unwrap();
+        }
+        writeln!(out, "    my_chip8.v = {};", bytes(&pre.v)).unwrap();
+        writeln!(out, "    my_chip8.i = {:#05x};", pre.i).unwrap();
+        if pre.sp > 0 {
+            writeln!(out, "    my_chip8.sp = {};", pre.sp).unwrap();
+            writeln!(out, "    my_chip8.stack[..{}].copy_from_slice(&{});", pre.sp, words(&pre.stack[..pre.sp])).unwrap();
+        }
+        if pre.delay_timer > 0 {
+            writeln!(out, "    my_chip8.delay_timer = {};", pre.delay_timer).unwrap();
+        }
+        if pre.sound_timer > 0 {
+            writeln!(out, "    my_chip8.sound_timer = {};", pre.sound_timer).unwrap();
+        }
+        for (key, _) in pre.key.iter().enumerate().filter(|(_, &held)| held) {
+            writeln!(out, "    my_chip8.key[{:#x}] = true;", key).unwrap();
+        }
+        let pre_lit = self.lit_cells(pre);
+        if !pre_lit.is_empty() {
+            writeln!(out, "    for &(x, y) in {:?}.iter() {{", pre_lit).unwrap();
+            writeln!(out, "        my_chip8.set_pixel(x, y, 1);").unwrap();
+            writeln!(out, "    }}").unwrap();
+        }
+
+        writeln!(out, "    my_chip8.emulate_cycle();").unwrap();
+
+        writeln!(out, "    assert_eq!(my_chip8.pc, {:#05x});", post.pc).unwrap();
+        writeln!(out, "    assert_eq!(my_chip8.v, {});", bytes(&post.v)).unwrap();
+        writeln!(out, "    assert_eq!(my_chip8.i, {:#05x});", post.i).unwrap();
+        if post.sp != pre.sp || post.sp > 0 {
+            writeln!(out, "    assert_eq!(my_chip8.sp, {});", post.sp).unwrap();
+        }
+        if post.sp > 0 {
+            writeln!(out, "    assert_eq!(my_chip8.stack[..{}], {});", post.sp, words(&post.stack[..post.sp])).unwrap();
+        }
+        if post.delay_timer != pre.delay_timer {
+            writeln!(out, "    assert_eq!(my_chip8.delay_timer, {});", post.delay_timer).unwrap();
+        }
+        if post.sound_timer != pre.sound_timer {
+            writeln!(out, "    assert_eq!(my_chip8.sound_timer, {});", post.sound_timer).unwrap();
+        }
+        let writes = post.writes(&self.fresh);
+        if let (Some(&start), Some(&end)) = (writes.first(), writes.last()) {
+            writeln!(out, "    assert_eq!(my_chip8.memory[{:#05x}..{:#05x}], {});", start, end + 1, bytes(&post.memory[start..=end])).unwrap();
+        }
+        if self.class == "Dxyn" || self.class == "00E0" {
+            writeln!(out, "    assert_eq!(lit(&my_chip8), {});", pairs(&lit(&post.gfx))).unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+}
+
+// lit pixels as (x, y), column by column
+fn lit(gfx: &[[u8; 128]; 64]) -> Vec<(usize, usize)> {
+    (0..WIDTH as usize)
+        .flat_map(|x| (0..HEIGHT as usize).map(move |y| (x, y)))
+        .filter(|&(x, y)| gfx[y][x] != 0)
+        .collect()
+}
+
+fn bytes(data: &[u8]) -> String {
+    let items: Vec<String> = data.iter().map(|byte| format!("{:#04x}", byte)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn words(data: &[u16]) -> String {
+    let items: Vec<String> = data.iter().map(|word| format!("{:#05x}", word)).collect();
+    format!("[{}]", items.join(", "))
+}
+
+fn pairs(cells: &[(usize, usize)]) -> String {
+    if cells.is_empty() {
+        return "Vec::<(usize, usize)>::new()".to_string();
+    }
+    format!("vec!{:?}", cells)
+}
+
+// run up to `cycles` instructions, keeping the first execution of each
+// instruction class that a fresh machine can repeat exactly
+pub fn capture(my_chip8: &mut Chip8, cycles: usize, preset: Preset) -> Vec<Sample> {
+    let mut seen = BTreeSet::new();
+    let mut samples = Vec::new();
+    for _ in 0..cycles {
+        if my_chip8.exited || my_chip8.paused || my_chip8.error.is_some() {
+            break;
+        }
+        let pre = State::of(my_chip8);
+        let sample = Sample::new(pre, preset)
+            .filter(|sample| !seen.contains(sample.class) && !SKIPPED_CLASSES.contains(&sample.class));
+        my_chip8.emulate_cycle();
+        if let Some(sample) = sample {
+            if sample.matches(&State::of(my_chip8)) {
+                seen.insert(sample.class);
+                samples.push(sample);
+            }
+        }
+    }
+    samples
+}
+
+// the samples as a Rust test module, for pasting into test_opcodes.rs
+pub fn to_rust(samples: &[Sample], preset: Preset) -> String {
+    let mut out = String::new();
+    out.push_str("// generated by --gen-tests: one test per instruction class, replaying an\n");
+    out.push_str("// execution captured from a real run\n\n");
+    out.push_str("use crate::Chip8;\n");
+    out.push_str("use crate::quirks::Preset;\n\n");
+    out.push_str("// lit pixels as (x, y), column by column\n");
+    out.push_str("fn lit(my_chip8: &Chip8) -> Vec<(usize, usize)> {\n");
+    out.push_str("    (0..64).flat_map(|x| (0..32).map(move |y| (x, y))).filter(|&(x, y)| my_chip8.pixel(x, y) != 0).collect()\n");
+    out.push_str("}\n");
+    for sample in samples.iter() {
+        out.push('\n');
+        out.push_str(&sample.to_rust(preset));
+    }
+    out
+}
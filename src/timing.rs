@@ -0,0 +1,115 @@
+#[cfg(test)]
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use winit::event_loop::ControlFlow;
+use chip8::processor::TIMER_PERIOD;
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_timing.rs"]
+mod test_timing;
+
+// bounds and step for runtime speed changes, in instructions per second
+pub const MIN_SPEED: u64 = 60;
+pub const MAX_SPEED: u64 = 5000;
+pub const SPEED_STEP: u64 = 50;
+
+// source of the current time for the run loop, so timing can be driven by
+// hand in tests
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+// a clock that only moves when told to
+#[cfg(test)]
+pub struct MockClock {
+    now: Cell<Instant>,
+}
+
+#[cfg(test)]
+impl MockClock {
+
+    pub fn new() -> Self {
+        Self { now: Cell::new(Instant::now()) }
+    }
+
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+#[cfg(test)]
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+// says when the 60Hz timers are due to tick, going by a clock
+pub struct TimerTicker {
+    next: Instant,
+}
+
+impl TimerTicker {
+
+    pub fn new(clock: &dyn Clock) -> Self {
+        Self { next: clock.now() + TIMER_PERIOD }
+    }
+
+    // when the next tick is due
+    pub fn deadline(&self) -> Instant {
+        self.next
+    }
+
+    // true once per timer period
+    pub fn due(&mut self, clock: &dyn Clock) -> bool {
+        let now = clock.now();
+        if now < self.next {
+            return false;
+        }
+        self.next = next_timer_deadline(self.next, now);
+        true
+    }
+}
+
+// eco mode presents at most this many frames per second
+pub const ECO_FRAME_PERIOD: Duration = Duration::from_nanos(1_000_000_000 / 30);
+
+// deadline for the timer tick after the one due at `last`, skipping ahead
+// instead of bursting if the loop has fallen more than a tick behind
+pub fn next_timer_deadline(last: Instant, now: Instant) -> Instant {
+    let next = last + TIMER_PERIOD;
+    if next < now {
+        now + TIMER_PERIOD
+    } else {
+        next
+    }
+}
+
+// sleep until the next timer tick unless input arrives first
+pub fn tick_control_flow(deadline: Instant) -> ControlFlow {
+    ControlFlow::WaitUntil(deadline)
+}
+
+// clock speed after pressing the speed up (+1) or down (-1) hotkey
+pub fn step_speed(speed: u64, direction: i64) -> u64 {
+    let stepped = if direction < 0 {
+        speed.saturating_sub(SPEED_STEP)
+    } else {
+        speed + SPEED_STEP
+    };
+    stepped.clamp(MIN_SPEED, MAX_SPEED)
+}
+
+// how much longer to wait so at least `min` passes between presented frames
+pub fn frame_throttle(last_present: Instant, now: Instant, min: Duration) -> Duration {
+    (last_present + min).saturating_duration_since(now)
+}
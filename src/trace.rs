@@ -0,0 +1,106 @@
+use std::collections::VecDeque;
+use std::fmt;
+use crate::processor::Chip8;
+
+// configure test cases
+#[cfg(test)]
+#[path = "test_trace.rs"]
+mod test_trace;
+
+// machine state captured after each executed instruction
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct TraceRow {
+    pub pc:     u16,                        // address the instruction was fetched from
+    pub opcode: u16,
+    pub v:      [u8; 16],
+    pub i:      u16,
+    pub sp:     usize,
+}
+
+impl TraceRow {
+    fn step(chip8: &mut Chip8) -> Self {
+        let pc = chip8.pc;
+        chip8.emulate_cycle();
+        Self {
+            pc,
+            opcode: chip8.opcode,
+            v:      chip8.v,
+            i:      chip8.i,
+            sp:     chip8.sp,
+        }
+    }
+}
+
+impl fmt::Display for TraceRow {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#05x}  {:04x}  I={:03x} SP={:x}  V=", self.pc, self.opcode, self.i, self.sp)?;
+        for reg in self.v.iter() {
+            write!(f, "{:02x}", reg)?;
+        }
+        Ok(())
+    }
+}
+
+// one instruction in the machine's recent history, kept by Chip8 for
+// looking back at what led up to a fault
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TraceEntry {
+    pub pc:       u16,
+    pub opcode:   u16,
+    pub mnemonic: &'static str,
+}
+
+impl fmt::Display for TraceEntry {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:#05x}  {:04x}  {}", self.pc, self.opcode, self.mnemonic)
+    }
+}
+
+// the first point where two runs disagree
+
+pub struct Divergence {
+    pub cycle:   usize,
+    pub context: Vec<TraceRow>,             // matching rows leading up to the divergence
+    pub a:       TraceRow,
+    pub b:       TraceRow,
+}
+
+impl fmt::Display for Divergence {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "traces diverge at cycle {}", self.cycle)?;
+        for row in self.context.iter() {
+            writeln!(f, "    {}", row)?;
+        }
+        writeln!(f, "a > {}", self.a)?;
+        write!(f, "b > {}", self.b)
+    }
+}
+
+// run both machines in lockstep for up to `cycles` instructions and report
+// the first instruction where pc, opcode, or register state differs
+pub fn diff_traces(a: &mut Chip8, b: &mut Chip8, cycles: usize, context: usize) -> Option<Divergence> {
+    let mut history = VecDeque::with_capacity(context + 1);
+
+    for cycle in 0..cycles {
+        let row_a = TraceRow::step(a);
+        let row_b = TraceRow::step(b);
+
+        if row_a != row_b {
+            return Some(Divergence {
+                cycle,
+                context: history.into_iter().collect(),
+                a: row_a,
+                b: row_b,
+            });
+        }
+
+        history.push_back(row_a);
+        if history.len() > context {
+            history.pop_front();
+        }
+    }
+
+    None
+}
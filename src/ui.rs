@@ -0,0 +1,144 @@
+use egui::{ClippedPrimitive, Context, TexturesDelta, ViewportId};
+use egui_wgpu::{Renderer, ScreenDescriptor};
+use pixels::{wgpu, Pixels, PixelsContext};
+use winit::event::WindowEvent;
+use winit::event_loop::EventLoopWindowTarget;
+use winit::window::Window;
+use chip8::processor::Chip8;
+use chip8::quirks::IncrementI;
+use crate::timing::{MAX_SPEED, MIN_SPEED};
+
+// egui overlay drawn on top of the emulator output
+
+pub struct Framework {
+    egui_ctx:          Context,
+    egui_state:        egui_winit::State,
+    screen_descriptor: ScreenDescriptor,
+    renderer:          Renderer,
+    paint_jobs:        Vec<ClippedPrimitive>,
+    textures:          TexturesDelta,
+}
+
+impl Framework {
+
+    pub fn new<T>(event_loop: &EventLoopWindowTarget<T>, window: &Window, pixels: &Pixels) -> Self {
+        let size = window.inner_size();
+        let scale_factor = window.scale_factor() as f32;
+        let max_texture_size = pixels.device().limits().max_texture_dimension_2d as usize;
+
+        let egui_ctx = Context::default();
+        let egui_state = egui_winit::State::new(
+            egui_ctx.clone(),
+            ViewportId::ROOT,
+            event_loop,
+            Some(scale_factor),
+            Some(max_texture_size),
+        );
+
+        Self {
+            egui_ctx,
+            egui_state,
+            screen_descriptor: ScreenDescriptor {
+                size_in_pixels:   [size.width, size.height],
+                pixels_per_point: scale_factor,
+            },
+            renderer:          Renderer::new(pixels.device(), pixels.render_texture_format(), None, 1),
+            paint_jobs:        Vec::new(),
+            textures:          TexturesDelta::default(),
+        }
+    }
+
+    pub fn handle_event(&mut self, window: &Window, event: &WindowEvent) {
+        let _ = self.egui_state.on_window_event(window, event);
+    }
+
+    pub fn resize(&mut self, width: u32, height: u32) {
+        if width > 0 && height > 0 {
+            self.screen_descriptor.size_in_pixels = [width, height];
+        }
+    }
+
+    pub fn prepare(&mut self, window: &Window, my_chip8: &mut Chip8) {
+        // build the panel; changes apply to the running machine immediately
+        let raw_input = self.egui_state.take_egui_input(window);
+        let output = self.egui_ctx.run(raw_input, |ctx| {
+            egui::Window::new("chip8").show(ctx, |ui| {
+                ui.add(egui::Slider::new(&mut my_chip8.clock_speed, MIN_SPEED..=MAX_SPEED)
+                    .text("instructions/s"));
+
+                ui.separator();
+                let quirks = &mut my_chip8.quirks;
+                ui.checkbox(&mut quirks.logic_resets_vf, "8xy1/2/3 clear VF");
+                ui.checkbox(&mut quirks.shift_uses_vy, "8xy6/8xyE shift Vy");
+                ui.checkbox(&mut quirks.jump_uses_vx, "Bxnn jumps to xnn + Vx");
+                ui.checkbox(&mut quirks.wrap_x, "sprites wrap horizontally");
+                ui.checkbox(&mut quirks.wrap_y, "sprites wrap vertically");
+                ui.checkbox(&mut quirks.fx1e_sets_vf, "Fx1E sets VF on overflow");
+                ui.checkbox(&mut quirks.hires_switch_clears, "00FE/00FF clear the display");
+                egui::ComboBox::from_label("Fx55/Fx65 advance I")
+                    .selected_text(increment_name(quirks.load_store_increment))
+                    .show_ui(ui, |ui| {
+                        for &increment in &[IncrementI::Unchanged, IncrementI::ByX, IncrementI::ByXPlusOne] {
+                            ui.selectable_value(&mut quirks.load_store_increment, increment, increment_name(increment));
+                        }
+                    });
+
+                ui.separator();
+                let palette = &mut my_chip8.palette;
+                for (color, label) in [
+                    (&mut palette.foreground, "foreground"),
+                    (&mut palette.background, "background"),
+                    (&mut palette.second,     "second plane"),
+                    (&mut palette.blend,      "both planes"),
+                ] {
+                    ui.horizontal(|ui| {
+                        ui.color_edit_button_srgba_unmultiplied(color);
+                        ui.label(label);
+                    });
+                }
+            });
+        });
+
+        self.textures.append(output.textures_delta);
+        self.egui_state.handle_platform_output(window, output.platform_output);
+        self.paint_jobs = self.egui_ctx.tessellate(output.shapes, self.screen_descriptor.pixels_per_point);
+    }
+
+    pub fn render(&mut self, encoder: &mut wgpu::CommandEncoder, render_target: &wgpu::TextureView, context: &PixelsContext) {
+        for (id, image_delta) in &self.textures.set {
+            self.renderer.update_texture(&context.device, &context.queue, *id, image_delta);
+        }
+        self.renderer.update_buffers(&context.device, &context.queue, encoder, &self.paint_jobs, &self.screen_descriptor);
+
+        {
+            let mut rpass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("egui"),
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: render_target,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Load,
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            self.renderer.render(&mut rpass, &self.paint_jobs, &self.screen_descriptor);
+        }
+
+        let textures = std::mem::take(&mut self.textures);
+        for id in &textures.free {
+            self.renderer.free_texture(id);
+        }
+    }
+}
+
+fn increment_name(increment: IncrementI) -> &'static str {
+    match increment {
+        IncrementI::Unchanged  => "no",
+        IncrementI::ByX        => "by x",
+        IncrementI::ByXPlusOne => "by x + 1",
+    }
+}
@@ -0,0 +1,46 @@
+use std::convert::TryFrom;
+
+// data watchpoints: pause the machine when a register or memory byte takes
+// on a value
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Watch {
+    Register { reg: usize, value: u8 },
+    Memory { addr: u16, value: u8 },
+}
+
+impl Watch {
+
+    // `VA==0` for a register, `0x300==5` for a memory byte
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let invalid = || format!("Invalid watchpoint: {} (expected VX==value or addr==value)", text);
+        let (target, value) = text.split_once("==").ok_or_else(invalid)?;
+        let value = parse_number(value).and_then(|v| u8::try_from(v).ok()).ok_or_else(invalid)?;
+
+        match target.strip_prefix('V').or_else(|| target.strip_prefix('v')) {
+            Some(reg) => {
+                let reg = usize::from_str_radix(reg, 16).ok().filter(|r| *r < 16).ok_or_else(invalid)?;
+                Ok(Watch::Register { reg, value })
+            }
+            None => {
+                let addr = parse_number(target).filter(|a| *a < 4096).ok_or_else(invalid)?;
+                Ok(Watch::Memory { addr: addr as u16, value })
+            }
+        }
+    }
+
+    pub fn holds(&self, v: &[u8; 16], memory: &[u8; 4096]) -> bool {
+        match *self {
+            Watch::Register { reg, value } => v[reg] == value,
+            Watch::Memory { addr, value } => memory[addr as usize] == value,
+        }
+    }
+}
+
+// decimal, or hex with a 0x prefix
+pub fn parse_number(text: &str) -> Option<u32> {
+    match text.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => text.parse().ok(),
+    }
+}
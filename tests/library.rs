@@ -0,0 +1,18 @@
+// the core is usable from outside the crate, without the windowed frontend
+use chip8::{Chip8, HEIGHT, WIDTH};
+
+#[test]
+fn test_run_rom_through_library() {
+    // draw the "0" glyph at (0, 0), then spin
+    let rom = [0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0x12, 0x06];
+
+    let mut my_chip8 = Chip8::initialize();
+    my_chip8.load_fontset();
+    my_chip8.load_program_bytes(&rom).unwrap();
+    assert_eq!(my_chip8.run_cycles(4), 0x206);
+
+    assert_eq!(my_chip8.resolution(), (WIDTH, HEIGHT));
+    // top row of "0" is 0xF0
+    let top: Vec<u8> = (0..8).map(|x| my_chip8.pixel(x, 0)).collect();
+    assert_eq!(top, [1, 1, 1, 1, 0, 0, 0, 0]);
+}